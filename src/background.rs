@@ -0,0 +1,201 @@
+//! Writes lines on a background thread.
+//!
+//! The default sink writes each line to stderr while the printing macro waits,
+//! which can cause hitches when many values change at once. After [`start`],
+//! a background thread writes each line with its prefixes and color to the
+//! [writer](crate::write::set_writer).
+//!
+//! Values are still formatted on the calling thread: a macro only borrows its
+//! value, so it queues the text. A value that is slow to format slows the
+//! macro with or without the background thread.
+//!
+//! Queued lines are written when the guard that [`start`] returns is dropped,
+//! when [`flush`] is called, when a thread panics, and on unix and windows
+//! when the program exits, including by [`std::process::exit`].
+//!
+//! ```rust
+//! use dbg_if::{background, dbg_if_ne};
+//!
+//! let _guard = background::start();
+//! for i in 0..1000u32 {
+//!     dbg_if_ne!(i / 100, u32); // Does not wait for stderr.
+//! }
+//! // Dropping `_guard` writes what is queued.
+//! ```
+use crate::{
+    prefix::Stamp,
+    write::{self, Location, Record},
+};
+use std::{
+    cell::Cell,
+    fmt,
+    string::{String, ToString},
+    sync::{
+        mpsc::{self, Sender},
+        OnceLock,
+    },
+};
+
+enum Message {
+    Line(Line),
+    Flush(Sender<()>),
+}
+
+/// A record whose values were formatted on the calling thread.
+struct Line {
+    stamp: Stamp,
+    location: Location,
+    expr: String,
+    kind: Kind,
+}
+
+enum Kind {
+    Value(String),
+    Change {
+        index: usize,
+        old: Option<Formatted>,
+        new: Option<Formatted>,
+    },
+    Location,
+}
+
+std::thread_local! {
+    static IS_WRITER: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Writes queued lines when dropped.
+#[must_use = "queued lines are written when the guard is dropped"]
+#[derive(Debug)]
+pub struct FlushGuard {
+    _private: (),
+}
+
+impl Drop for FlushGuard {
+    fn drop(&mut self) {
+        flush();
+    }
+}
+
+/// Starts the background thread and sets the sink to queue records for it.
+///
+/// Calling this again returns another guard for the same thread.
+pub fn start() -> FlushGuard {
+    sender();
+    write::set_sink(sink);
+    FlushGuard { _private: () }
+}
+
+/// Waits until the lines queued so far are written.
+pub fn flush() {
+    let Some(sender) = SENDER.get() else {
+        return;
+    };
+    if IS_WRITER.try_with(Cell::get).unwrap_or(true) {
+        return;
+    }
+    let (done, wait) = mpsc::channel();
+    if sender.send(Message::Flush(done)).is_ok() {
+        let _ = wait.recv();
+    }
+}
+
+/// Queues the record for the background thread. This is the
+/// [sink](crate::write::set_sink) that [`start`] sets.
+pub fn sink(record: &Record<'_>) {
+    let kind = match *record {
+        Record::Value { value, .. } => Kind::Value(value.to_string()),
+        Record::Change { index, old, new, .. } => Kind::Change {
+            index,
+            old: old.map(|old| Formatted(std::format!("{:?}", old))),
+            new: new.map(|new| Formatted(std::format!("{:?}", new))),
+        },
+        Record::Location { .. } => Kind::Location,
+    };
+    let expr = match *record {
+        Record::Value { expr, .. } | Record::Change { expr, .. } => expr.into(),
+        Record::Location { .. } => String::new(),
+    };
+    let line = Line {
+        stamp: Stamp::now(),
+        location: record.location(),
+        expr,
+        kind,
+    };
+    if let Err(mpsc::SendError(Message::Line(line))) = sender().send(Message::Line(line)) {
+        // The background thread is gone, so write it here.
+        line.write();
+    }
+}
+
+static SENDER: OnceLock<Sender<Message>> = OnceLock::new();
+
+fn sender() -> &'static Sender<Message> {
+    SENDER.get_or_init(|| {
+        let (sender, receiver) = mpsc::channel();
+        std::thread::Builder::new()
+            .name("dbg_if".into())
+            .spawn(move || {
+                IS_WRITER.with(|is_writer| is_writer.set(true));
+                for message in receiver {
+                    match message {
+                        Message::Line(line) => line.write(),
+                        Message::Flush(done) => {
+                            let _ = done.send(());
+                        }
+                    }
+                }
+            })
+            .expect("spawn dbg_if thread");
+        #[cfg(any(unix, windows))]
+        {
+            extern "C" {
+                fn atexit(callback: extern "C" fn()) -> core::ffi::c_int;
+            }
+            extern "C" fn on_exit() {
+                flush();
+            }
+            // SAFETY: `on_exit` neither unwinds nor calls `exit`.
+            unsafe {
+                atexit(on_exit);
+            }
+        }
+        let hook = std::panic::take_hook();
+        std::panic::set_hook(std::boxed::Box::new(move |info| {
+            flush();
+            hook(info);
+        }));
+        sender
+    })
+}
+
+impl Line {
+    fn write(&self) {
+        let expr = self.expr.as_str();
+        let location = self.location;
+        let record = match &self.kind {
+            Kind::Value(value) => Record::Value {
+                location,
+                expr,
+                value,
+            },
+            Kind::Change { index, old, new } => Record::Change {
+                location,
+                expr,
+                index: *index,
+                old: old.as_ref().map(|old| old as &dyn fmt::Debug),
+                new: new.as_ref().map(|new| new as &dyn fmt::Debug),
+            },
+            Kind::Location => Record::Location { location },
+        };
+        write::write_stamped(self.stamp, &record);
+    }
+}
+
+/// Writes an already formatted element as is.
+struct Formatted(String);
+
+impl fmt::Debug for Formatted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
@@ -0,0 +1,165 @@
+//! Captures what the printing macros would print on this thread.
+//!
+//! Unlike redirecting stderr, a capture is per thread, so tests that run in
+//! parallel do not see each other's records.
+//!
+//! ```rust
+//! use dbg_if::{capture, dbg_if_ne};
+//! fn f(x: u8) -> u8 {
+//!     dbg_if_ne!(x, u8)
+//! }
+//! let records = capture(|| {
+//!     f(1);
+//!     f(1);
+//!     f(2);
+//! });
+//! let values: Vec<_> = records.iter().map(|r| r.value.as_str()).collect();
+//! assert_eq!(values, ["1", "2"]);
+//! ```
+use crate::write::{Location, Record};
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{cell::RefCell, fmt, marker::PhantomData};
+
+std::thread_local! {
+    static CAPTURES: RefCell<Vec<Vec<DbgRecord>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// A record that was captured.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DbgRecord {
+    /// Where the macro was called.
+    pub location: Location,
+    /// The expression, e.g., `x` or `v[1]` for an element that changed. It is
+    /// empty for `dbg_once!()`.
+    pub expr: String,
+    /// The value as formatted by [`format`](mod@crate::format) or the element
+    /// now.
+    pub value: String,
+    /// The element before if this is an element that changed.
+    pub old: Option<String>,
+}
+
+/// Writes the record without its location, e.g., `x = 1` or `v[1]: 2 -> 3`.
+impl fmt::Display for DbgRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.old {
+            Some(old) => write!(f, "{}: {} -> {}", self.expr, old, self.value),
+            None if self.expr.is_empty() => Ok(()),
+            None => write!(f, "{} = {}", self.expr, self.value),
+        }
+    }
+}
+
+impl From<&Record<'_>> for DbgRecord {
+    fn from(record: &Record<'_>) -> Self {
+        fn elem(value: Option<&dyn fmt::Debug>) -> String {
+            value.map_or_else(|| "(none)".into(), |v| format!("{:?}", v))
+        }
+        let location = record.location();
+        match *record {
+            Record::Value { expr, value, .. } => DbgRecord {
+                location,
+                expr: expr.into(),
+                value: value.to_string(),
+                old: None,
+            },
+            Record::Change {
+                expr,
+                index,
+                old,
+                new,
+                ..
+            } => DbgRecord {
+                location,
+                expr: format!("{}[{}]", expr, index),
+                value: elem(new),
+                old: Some(elem(old)),
+            },
+            Record::Location { .. } => DbgRecord {
+                location,
+                expr: String::new(),
+                value: String::new(),
+                old: None,
+            },
+        }
+    }
+}
+
+/// Returns the records the printing macros made on this thread while `f` ran
+/// instead of printing them.
+pub fn capture<F: FnOnce()>(f: F) -> Vec<DbgRecord> {
+    let capture = Capture::start();
+    f();
+    capture.finish()
+}
+
+/// Captures records on this thread until it is finished or dropped.
+///
+/// Captures nest; records go to the most recent one.
+///
+/// ```rust
+/// use dbg_if::{capture::Capture, dbg_once};
+/// let capture = Capture::start();
+/// dbg_once!(1);
+/// assert_eq!(capture.finish()[0].to_string(), "1 = 1");
+/// ```
+#[derive(Debug)]
+pub struct Capture {
+    depth: usize,
+    // Must finish on the thread it started on.
+    _not_send: PhantomData<*const ()>,
+}
+
+impl Capture {
+    /// Starts capturing records on this thread.
+    pub fn start() -> Self {
+        let depth = CAPTURES.with_borrow_mut(|captures| {
+            captures.push(Vec::new());
+            captures.len()
+        });
+        Capture {
+            depth,
+            _not_send: PhantomData,
+        }
+    }
+
+    /// Returns the records captured so far, or none if a capture started
+    /// before this one was dropped, which ended this one too.
+    pub fn records(&self) -> Vec<DbgRecord> {
+        CAPTURES.with_borrow(|captures| captures.get(self.depth - 1).cloned().unwrap_or_default())
+    }
+
+    /// Stops capturing and returns the records.
+    pub fn finish(self) -> Vec<DbgRecord> {
+        CAPTURES.with_borrow_mut(|captures| {
+            if captures.len() < self.depth {
+                return Vec::new();
+            }
+            captures.truncate(self.depth);
+            captures.pop().unwrap_or_default()
+        })
+    }
+}
+
+impl Drop for Capture {
+    fn drop(&mut self) {
+        let _ = CAPTURES.try_with(|captures| captures.borrow_mut().truncate(self.depth - 1));
+    }
+}
+
+/// Keeps the record if this thread is capturing and returns true.
+pub(crate) fn keep(record: &Record<'_>) -> bool {
+    CAPTURES
+        .try_with(|captures| match captures.borrow_mut().last_mut() {
+            Some(records) => {
+                records.push(record.into());
+                true
+            }
+            None => false,
+        })
+        .unwrap_or(false)
+}
@@ -0,0 +1,73 @@
+//! ANSI colored output for the printing macros.
+//!
+//! With the "color" feature, the location is dimmed, the expression is bold,
+//! the value is highlighted, and an old value is struck through when elements
+//! are diffed. Locations are padded so that expressions line up.
+//!
+//! By default color is only used when writing to stderr, stderr is a terminal,
+//! and the `NO_COLOR` environment variable is not set. Use [`set_color`] to
+//! override that.
+//!
+//! ```rust
+//! use dbg_if::color::{set_color, Color};
+//! set_color(Color::Never);
+//! ```
+use std::{
+    io::IsTerminal,
+    sync::{
+        atomic::{AtomicU8, AtomicUsize, Ordering},
+        OnceLock,
+    },
+};
+
+/// When to color output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Color {
+    /// Color when writing to a terminal and `NO_COLOR` is not set.
+    #[default]
+    Auto,
+    /// Always color.
+    Always,
+    /// Never color.
+    Never,
+}
+
+static COLOR: AtomicU8 = AtomicU8::new(Color::Auto as u8);
+static LOCATION_WIDTH: AtomicUsize = AtomicUsize::new(0);
+
+/// Sets when to color output.
+pub fn set_color(color: Color) {
+    COLOR.store(color as u8, Ordering::Relaxed);
+}
+
+/// Returns true if output should be colored.
+pub(crate) fn enabled(default_writer: bool) -> bool {
+    const ALWAYS: u8 = Color::Always as u8;
+    const NEVER: u8 = Color::Never as u8;
+    match COLOR.load(Ordering::Relaxed) {
+        ALWAYS => true,
+        NEVER => false,
+        _ => default_writer && auto(),
+    }
+}
+
+fn auto() -> bool {
+    static AUTO: OnceLock<bool> = OnceLock::new();
+    *AUTO.get_or_init(|| {
+        std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty()) && std::io::stderr().is_terminal()
+    })
+}
+
+/// Returns the padding that aligns a location of this width with the widest
+/// one seen so far.
+pub(crate) fn padding(width: usize) -> usize {
+    LOCATION_WIDTH
+        .fetch_max(width, Ordering::Relaxed)
+        .saturating_sub(width)
+}
+
+pub(crate) const DIM: &str = "\x1b[2m";
+pub(crate) const BOLD: &str = "\x1b[1m";
+pub(crate) const NEW: &str = "\x1b[32m";
+pub(crate) const OLD: &str = "\x1b[9;31m";
+pub(crate) const RESET: &str = "\x1b[0m";
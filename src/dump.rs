@@ -0,0 +1,86 @@
+//! Dumps what the printing macros saw when a thread panics.
+//!
+//! A panic deep inside a loop often depends on values that a filter
+//! suppressed. After [`install_panic_hook`], each call site keeps the last
+//! value it was reached with, whether it printed or not, and the most recent
+//! records are kept. A panic then writes both to the
+//! [writer](crate::write::set_writer) after the panic message.
+//!
+//! ```rust,should_panic
+//! use dbg_if::{dbg_if_ne, dump};
+//! dump::install_panic_hook(10);
+//! for i in 0..=100u8 {
+//!     dbg_if_ne!(i / 50, u8);
+//!     assert!(i < 100);
+//! }
+//! // Outputs: dbg_if: last values
+//! //          location           expr   hits  prints  value
+//! //          src/dump.rs:5:5    i / 50  101       3  2
+//! //          dbg_if: recent records
+//! //          [src/dump.rs:5:5] i / 50 = 0
+//! //          [src/dump.rs:5:5] i / 50 = 1
+//! //          [src/dump.rs:5:5] i / 50 = 2
+//! ```
+//!
+//! Keeping the last value formats it each time a call site is reached, so it
+//! only happens once the hook is installed.
+use crate::{
+    recent,
+    report::{self, write_table},
+};
+use std::{
+    boxed::Box,
+    string::{String, ToString},
+    sync::Once,
+    vec::Vec,
+};
+
+static HOOK: Once = Once::new();
+
+/// Keeps the last value of each call site and the last `recent` records, and
+/// sets a panic hook that calls [`dump`] after the prior hook. Calling it again
+/// only changes how many records are kept.
+pub fn install_panic_hook(recent: usize) {
+    report::set_observe(true);
+    recent::set_capacity(recent);
+    HOOK.call_once(|| {
+        let hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            hook(info);
+            dump();
+        }));
+    });
+}
+
+/// Returns true if [`install_panic_hook`] set its hook, which also writes the
+/// records kept.
+pub(crate) fn is_installed() -> bool {
+    HOOK.is_completed()
+}
+
+/// Writes the last value of each call site and the records kept; see
+/// [`recent::set_capacity`].
+pub fn dump() {
+    let writer = crate::write::writer();
+    writer(format_args!("dbg_if: last values"));
+    let rows: Vec<[String; 5]> = report::sorted()
+        .into_iter()
+        .map(|site| {
+            [
+                site.location().to_string(),
+                site.expr().into(),
+                site.hits().to_string(),
+                site.prints().to_string(),
+                site.try_last_observed().unwrap_or_else(|| "(busy)".into()),
+            ]
+        })
+        .collect();
+    write_table(writer, ["location", "expr", "hits", "prints", "value"], &rows);
+    let recent = recent::capacity();
+    if recent > 0 {
+        writer(format_args!("dbg_if: recent records"));
+        if !recent::try_dump_recent(recent) {
+            writer(format_args!("(busy)"));
+        }
+    }
+}
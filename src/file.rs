@@ -0,0 +1,200 @@
+//! Writes output to a file that rotates when it grows too large.
+//!
+//! Call [`open`] or set the `DBG_IF_FILE` environment variable to a path to
+//! write there instead of stderr. `DBG_IF_FILE_MAX_BYTES` and
+//! `DBG_IF_FILE_MAX_FILES` override the default [`Rotation`].
+//!
+//! ```sh
+//! DBG_IF_FILE=/tmp/dbg.log cargo run
+//! ```
+//!
+//! Writes are buffered and flushed with the next line after 100 ms, when a
+//! thread panics, and on unix and windows when the program exits, including by
+//! [`std::process::exit`]. Call [`flush`] to write them sooner, and before
+//! exiting on other targets.
+//!
+//! ```rust,no_run
+//! use dbg_if::{dbg_once, file::{self, Rotation}};
+//! file::open("/tmp/dbg.log", Rotation { max_bytes: 1 << 20, max_files: 2 }).unwrap();
+//! dbg_once!(1); // Written by the time the program exits.
+//! ```
+use crate::lock::Lock;
+use std::{
+    fmt::{self, Write as _},
+    fs::{self, File, OpenOptions},
+    io::{self, BufWriter, Write as _},
+    path::{Path, PathBuf},
+    sync::{Once, OnceLock},
+    time::{Duration, Instant},
+};
+
+/// When to rotate the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rotation {
+    /// Rotate before the file grows past this many bytes.
+    pub max_bytes: u64,
+    /// Keep this many old files, e.g., `dbg.log.1` and `dbg.log.2`.
+    pub max_files: usize,
+}
+
+impl Default for Rotation {
+    /// Rotates at 10 MiB and keeps 3 old files.
+    fn default() -> Self {
+        Rotation {
+            max_bytes: 10 << 20,
+            max_files: 3,
+        }
+    }
+}
+
+const FLUSH_EVERY: Duration = Duration::from_millis(100);
+
+static FILE: Lock<Option<RotatingFile>> = Lock::new(None);
+
+/// Opens `path` for appending and writes each line to it from now on.
+pub fn open(path: impl AsRef<Path>, rotation: Rotation) -> io::Result<()> {
+    let file = RotatingFile::open(path.as_ref().to_owned(), rotation)?;
+    flush_at_exit();
+    if let Some(mut old) = FILE.lock().replace(file) {
+        let _ = old.file.flush();
+    }
+    crate::write::set_writer(write_line);
+    Ok(())
+}
+
+/// Writes any buffered lines to the file.
+pub fn flush() {
+    if let Some(file) = FILE.lock().as_mut() {
+        let _ = file.file.flush();
+    }
+}
+
+/// Writes a line to the open file. This is the [writer](crate::write::set_writer)
+/// that [`open`] sets.
+pub fn write_line(line: fmt::Arguments<'_>) {
+    if let Some(file) = FILE.lock().as_mut() {
+        let _ = file.write_line(line);
+    }
+}
+
+/// Returns true if `DBG_IF_FILE` named a file that was opened.
+pub(crate) fn from_env() -> bool {
+    static FROM_ENV: OnceLock<bool> = OnceLock::new();
+    *FROM_ENV.get_or_init(|| {
+        let Some(path) = std::env::var_os("DBG_IF_FILE") else {
+            return false;
+        };
+        fn var<T: std::str::FromStr>(name: &str) -> Option<T> {
+            std::env::var(name).ok()?.parse().ok()
+        }
+        let default = Rotation::default();
+        let rotation = Rotation {
+            max_bytes: var("DBG_IF_FILE_MAX_BYTES").unwrap_or(default.max_bytes),
+            max_files: var("DBG_IF_FILE_MAX_FILES").unwrap_or(default.max_files),
+        };
+        match RotatingFile::open(PathBuf::from(path), rotation) {
+            Ok(file) => {
+                flush_at_exit();
+                *FILE.lock() = Some(file);
+                true
+            }
+            Err(e) => {
+                std::eprintln!("dbg_if: cannot open DBG_IF_FILE: {}", e);
+                false
+            }
+        }
+    })
+}
+
+/// Flushes the file when a thread panics and, on unix and windows, when the
+/// program exits, the first time it is called.
+fn flush_at_exit() {
+    #[cfg(any(unix, windows))]
+    extern "C" {
+        fn atexit(callback: extern "C" fn()) -> core::ffi::c_int;
+    }
+    extern "C" fn on_exit() {
+        // Lines still queued for the background thread go to the file first.
+        crate::background::flush();
+        // Another thread may hold the file while the process exits.
+        if let Some(Some(file)) = FILE.try_lock().as_deref_mut() {
+            let _ = file.file.flush();
+        }
+    }
+    static ONCE: Once = Once::new();
+    ONCE.call_once(|| {
+        #[cfg(any(unix, windows))]
+        // SAFETY: `on_exit` neither unwinds nor calls `exit`.
+        unsafe {
+            atexit(on_exit);
+        }
+        let hook = std::panic::take_hook();
+        std::panic::set_hook(std::boxed::Box::new(move |info| {
+            hook(info);
+            on_exit();
+        }));
+    });
+}
+
+struct RotatingFile {
+    path: PathBuf,
+    rotation: Rotation,
+    file: BufWriter<File>,
+    size: u64,
+    flushed: Instant,
+    line: String,
+}
+
+impl RotatingFile {
+    fn open(path: PathBuf, rotation: Rotation) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(RotatingFile {
+            path,
+            rotation,
+            file: BufWriter::new(file),
+            size,
+            flushed: Instant::now(),
+            line: String::new(),
+        })
+    }
+
+    fn write_line(&mut self, line: fmt::Arguments<'_>) -> io::Result<()> {
+        self.line.clear();
+        let _ = writeln!(self.line, "{}", line);
+        let len = self.line.len() as u64;
+        if self.size > 0 && self.size + len > self.rotation.max_bytes {
+            self.rotate()?;
+        }
+        self.file.write_all(self.line.as_bytes())?;
+        self.size += len;
+        if self.flushed.elapsed() >= FLUSH_EVERY {
+            self.file.flush()?;
+            self.flushed = Instant::now();
+        }
+        Ok(())
+    }
+
+    /// Renames `path.1` to `path.2` and so on, then `path` to `path.1`, and
+    /// starts a new file.
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        let old = |n: usize| {
+            let mut path = self.path.clone().into_os_string();
+            path.push(format!(".{}", n));
+            PathBuf::from(path)
+        };
+        if self.rotation.max_files > 0 {
+            for n in (1..self.rotation.max_files).rev() {
+                let from = old(n);
+                if from.exists() {
+                    fs::rename(from, old(n + 1))?;
+                }
+            }
+            fs::rename(&self.path, old(1))?;
+        }
+        self.file = BufWriter::new(File::create(&self.path)?);
+        self.size = 0;
+        Ok(())
+    }
+}
@@ -0,0 +1,741 @@
+//! Filters decide whether a call site should print.
+//!
+//! The [`dbg_with!`](crate::dbg_with!) and [`with!`](crate::with!) macros, and
+//! the [`dbg_if!`](crate::dbg_if!) facade, accept a small algebra of filters as
+//! their second argument. Each stateful filter is allocated as a static at its
+//! call site; the dedicated macros like [`was_ne!`](crate::was_ne!) are built
+//! on the same filters.
+//!
+//! | Filter          | Prints when                                        |
+//! |-----------------|----------------------------------------------------|
+//! | `Every`         | always                                             |
+//! | `Once`          | the first time                                     |
+//! | `EveryN(n)`     | the 1st, (n + 1)th, (2n + 1)th, ... time           |
+//! | `IfNe(type)`    | the value is not equal to the last printed value   |
+//! | `IfNe(type, f)` | the `ne` function `f(last, new)` returns true      |
+//! | `IfHashNe`      | the value's hash is not equal to the last printed  |
+//! | `When(pred)`    | the predicate `pred(&value)` returns true          |
+//!
+//! Filters combine with `&` and `|` and group with parentheses. `&` binds
+//! tighter than `|`. Both evaluate left to right and short-circuit, so a
+//! stateful filter on the right only observes values that passed the left.
+//!
+//! ```rust
+//! use dbg_if::dbg_if as dbg;
+//! for i in 0..100u8 {
+//!     dbg!(i / 10, IfNe(u8) & EveryN(2));
+//!     dbg!(i, When(|v| *v > 50) & Once | When(|v| *v == 99));
+//! }
+//! ```
+//!
+//! # Custom filters
+//!
+//! Any other path is treated as a user-defined filter type. It must implement
+//! [`DbgFilter`] and provide a `const fn new() -> Self` so that it can be
+//! allocated as a static at the call site.
+//!
+//! ```rust
+//! use dbg_if::{dbg_if as dbg, DbgFilter};
+//! use std::sync::atomic::{AtomicU32, Ordering};
+//!
+//! struct Rising(AtomicU32);
+//!
+//! impl Rising {
+//!     const fn new() -> Self {
+//!         Rising(AtomicU32::new(0))
+//!     }
+//! }
+//!
+//! impl DbgFilter<u32> for Rising {
+//!     fn should_print(&self, value: &u32) -> bool {
+//!         self.0.fetch_max(*value, Ordering::Relaxed) < *value
+//!     }
+//! }
+//!
+//! for x in [1, 3, 2, 5, 4] {
+//!     dbg!(x, Rising); // Outputs: x = 1, x = 3, x = 5
+//! }
+//! ```
+use crate::lock::Lock;
+#[cfg(feature = "alloc")]
+use alloc::{boxed::Box, vec::Vec};
+#[cfg(feature = "alloc")]
+use core::any::Any;
+use core::{
+    fmt,
+    hash::{Hash, Hasher},
+    ops::Deref,
+    sync::atomic::{
+        AtomicBool, AtomicI16, AtomicI32, AtomicI8, AtomicIsize, AtomicU16, AtomicU32, AtomicU8,
+        AtomicUsize, Ordering,
+    },
+};
+#[cfg(target_has_atomic = "64")]
+use core::sync::atomic::{AtomicI64, AtomicU64};
+
+/// Decides whether a value should be printed at a call site.
+pub trait DbgFilter<T: ?Sized> {
+    /// Returns true if the value should be printed.
+    fn should_print(&self, value: &T) -> bool;
+}
+
+impl<T: ?Sized, F: DbgFilter<T> + ?Sized> DbgFilter<T> for &F {
+    fn should_print(&self, value: &T) -> bool {
+        (**self).should_print(value)
+    }
+}
+
+/// Always prints.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Every;
+
+impl Every {
+    /// Create a filter.
+    pub const fn new() -> Self {
+        Every
+    }
+}
+
+impl<T: ?Sized> DbgFilter<T> for Every {
+    fn should_print(&self, _value: &T) -> bool {
+        true
+    }
+}
+
+/// Prints the first time only.
+#[derive(Debug)]
+pub struct Once {
+    first: AtomicBool,
+}
+
+impl Once {
+    /// Create a filter.
+    pub const fn new() -> Self {
+        Once {
+            first: AtomicBool::new(true),
+        }
+    }
+}
+
+impl Default for Once {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: ?Sized> DbgFilter<T> for Once {
+    fn should_print(&self, _value: &T) -> bool {
+        self.first.swap(false, Ordering::Relaxed)
+    }
+}
+
+/// Prints the first time and every `n`th time after that.
+#[derive(Debug)]
+pub struct EveryN {
+    n: usize,
+    count: AtomicUsize,
+}
+
+impl EveryN {
+    /// Create a filter that prints every `n`th time. Zero is treated as one.
+    pub const fn new(n: usize) -> Self {
+        EveryN {
+            n: if n == 0 { 1 } else { n },
+            count: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl<T: ?Sized> DbgFilter<T> for EveryN {
+    fn should_print(&self, _value: &T) -> bool {
+        self.count
+            .fetch_add(1, Ordering::Relaxed)
+            .is_multiple_of(self.n)
+    }
+}
+
+/// Prints when the value is not equal to the last printed value.
+///
+/// The type parameter is the atomic used for storage, e.g., `IfNe<AtomicU8>`
+/// watches `u8` values.
+#[derive(Debug)]
+pub struct IfNe<A> {
+    first: AtomicBool,
+    last: A,
+}
+
+impl<A: Atomic> IfNe<A> {
+    /// Returns true and stores `value` if this is the first value or if
+    /// `ne(last, value)` returns true.
+    pub fn changed_by<N>(&self, value: A::Value, ne: N) -> bool
+    where
+        N: Fn(A::Value, A::Value) -> bool,
+    {
+        let first = self.first.swap(false, Ordering::Relaxed);
+        self.last
+            .fetch_update(|v| (first || ne(v, value)).then_some(value))
+            .is_ok()
+    }
+}
+
+impl<A: Atomic> DbgFilter<A::Value> for IfNe<A>
+where
+    A::Value: PartialEq,
+{
+    fn should_print(&self, value: &A::Value) -> bool {
+        self.changed_by(*value, A::value_ne)
+    }
+}
+
+/// Prints when a custom `ne` function says the value changed.
+///
+/// This pairs the state of an [`IfNe`] filter, e.g., `&IfNe<A>`, with a
+/// function or closure of signature `Fn(T, T) -> bool`.
+#[derive(Debug)]
+pub struct IfNeBy<S, N> {
+    state: S,
+    ne: N,
+}
+
+impl<S, A, N> IfNeBy<S, N>
+where
+    S: Deref<Target = IfNe<A>>,
+    A: Atomic,
+    N: Fn(A::Value, A::Value) -> bool,
+{
+    /// Create a filter.
+    pub fn new(state: S, ne: N) -> Self {
+        IfNeBy { state, ne }
+    }
+}
+
+impl<S, A, N> DbgFilter<A::Value> for IfNeBy<S, N>
+where
+    S: Deref<Target = IfNe<A>>,
+    A: Atomic,
+    N: Fn(A::Value, A::Value) -> bool,
+{
+    fn should_print(&self, value: &A::Value) -> bool {
+        self.state.changed_by(*value, &self.ne)
+    }
+}
+
+/// Prints when the value's hash is not equal to the last printed value's hash.
+#[derive(Debug)]
+pub struct IfHashNe {
+    hash: U64Cell,
+}
+
+impl IfHashNe {
+    /// Create a filter.
+    pub const fn new() -> Self {
+        IfHashNe {
+            #[cfg(target_has_atomic = "64")]
+            hash: AtomicU64::new(0),
+            #[cfg(not(target_has_atomic = "64"))]
+            hash: Locked::new(),
+        }
+    }
+}
+
+impl Default for IfHashNe {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Hash + ?Sized> DbgFilter<T> for IfHashNe {
+    fn should_print(&self, value: &T) -> bool {
+        let mut s = DbgHasher::default();
+        value.hash(&mut s);
+        let current_hash = s.finish();
+        Atomic::fetch_update(&self.hash, |h| (h != current_hash).then_some(current_hash)).is_ok()
+    }
+}
+
+#[cfg(feature = "std")]
+type DbgHasher = std::hash::DefaultHasher;
+
+#[cfg(not(feature = "std"))]
+type DbgHasher = Fnv1a;
+
+/// The 64-bit FNV-1a hash for builds without `std`.
+#[cfg(not(feature = "std"))]
+struct Fnv1a(u64);
+
+#[cfg(not(feature = "std"))]
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Fnv1a(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl Hasher for Fnv1a {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+/// Prints when the predicate holds.
+#[derive(Debug, Clone, Copy)]
+pub struct When<P>(pub P);
+
+impl<P> When<P> {
+    /// Create a filter for values like `value`.
+    ///
+    /// This exists so that a closure's argument type can be inferred from the
+    /// value.
+    pub fn for_value<T: ?Sized>(_value: &T, pred: P) -> Self
+    where
+        P: Fn(&T) -> bool,
+    {
+        When(pred)
+    }
+}
+
+impl<T: ?Sized, P: Fn(&T) -> bool> DbgFilter<T> for When<P> {
+    fn should_print(&self, value: &T) -> bool {
+        (self.0)(value)
+    }
+}
+
+/// Prints when both filters would print.
+///
+/// The second filter is only consulted when the first would print.
+#[derive(Debug, Clone, Copy)]
+pub struct And<A, B>(pub A, pub B);
+
+impl<T: ?Sized, A: DbgFilter<T>, B: DbgFilter<T>> DbgFilter<T> for And<A, B> {
+    fn should_print(&self, value: &T) -> bool {
+        self.0.should_print(value) && self.1.should_print(value)
+    }
+}
+
+/// Prints when either filter would print.
+///
+/// The second filter is only consulted when the first would not print.
+#[derive(Debug, Clone, Copy)]
+pub struct Or<A, B>(pub A, pub B);
+
+impl<T: ?Sized, A: DbgFilter<T>, B: DbgFilter<T>> DbgFilter<T> for Or<A, B> {
+    fn should_print(&self, value: &T) -> bool {
+        self.0.should_print(value) || self.1.should_print(value)
+    }
+}
+
+/// Band state for [`hysteresis_ne_args!`](crate::hysteresis_ne_args!).
+///
+/// A value is in the low band below `low`, the high band above `high`, and
+/// otherwise in between. A change is only reported when the value crosses into
+/// the other outer band; staying in or returning to the middle is not a change.
+#[derive(Debug)]
+pub struct Hysteresis {
+    band: AtomicU8,
+}
+
+impl Hysteresis {
+    const UNKNOWN: u8 = 0;
+    const LOW: u8 = 1;
+    const MIDDLE: u8 = 2;
+    const HIGH: u8 = 3;
+
+    /// Create a band state.
+    pub const fn new() -> Self {
+        Hysteresis {
+            band: AtomicU8::new(Self::UNKNOWN),
+        }
+    }
+
+    /// Returns true if `new_value` crossed into another outer band.
+    ///
+    /// The band is initialized from `last_value` the first time it is called.
+    pub fn crossed<T: PartialOrd>(&self, last_value: T, new_value: T, low: T, high: T) -> bool {
+        let classify = |v: &T| {
+            if *v > high {
+                Self::HIGH
+            } else if *v < low {
+                Self::LOW
+            } else {
+                Self::MIDDLE
+            }
+        };
+        let mut band = self.band.load(Ordering::Relaxed);
+        if band == Self::UNKNOWN {
+            band = classify(&last_value);
+        }
+        let next = match classify(&new_value) {
+            Self::MIDDLE => band,
+            outer => outer,
+        };
+        self.band.store(next, Ordering::Relaxed);
+        next != band
+    }
+}
+
+impl Default for Hysteresis {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns true if the values are not equal, treating NaN as equal to NaN.
+///
+/// A value is considered NaN if it is not equal to itself. A change into or out
+/// of NaN is always reported. Otherwise `ne(last_value, new_value)` decides.
+///
+/// ```rust
+/// use dbg_if::filter::nan_ne;
+/// let ne = |a: f32, b: f32| (a - b).abs() > 1.0;
+/// assert!(!nan_ne(f32::NAN, f32::NAN, ne));
+/// assert!(nan_ne(1.0, f32::NAN, ne));
+/// assert!(nan_ne(f32::NAN, 1.0, ne));
+/// assert!(!nan_ne(1.0, 1.5, ne));
+/// ```
+#[allow(clippy::eq_op)]
+pub fn nan_ne<T, N>(last_value: T, new_value: T, ne: N) -> bool
+where
+    T: PartialEq,
+    N: FnOnce(T, T) -> bool,
+{
+    match (last_value != last_value, new_value != new_value) {
+        (true, true) => false,
+        (false, false) => ne(last_value, new_value),
+        _ => true,
+    }
+}
+
+/// Integers whose differences can be measured without overflow.
+pub trait IntDelta: Copy {
+    /// Returns the magnitude of `self`.
+    fn magnitude(self) -> u128;
+    /// Returns the magnitude of `self - other`.
+    fn delta(self, other: Self) -> u128;
+}
+
+macro_rules! impl_int_delta {
+    ($($type:ty),+ $(,)?) => {
+        $(
+            impl IntDelta for $type {
+                fn magnitude(self) -> u128 {
+                    self.unsigned_abs() as u128
+                }
+
+                fn delta(self, other: Self) -> u128 {
+                    self.abs_diff(other) as u128
+                }
+            }
+        )+
+    };
+}
+
+impl_int_delta!(i8, i16, i32, i64, isize);
+
+macro_rules! impl_uint_delta {
+    ($($type:ty),+ $(,)?) => {
+        $(
+            impl IntDelta for $type {
+                fn magnitude(self) -> u128 {
+                    self as u128
+                }
+
+                fn delta(self, other: Self) -> u128 {
+                    self.abs_diff(other) as u128
+                }
+            }
+        )+
+    };
+}
+
+impl_uint_delta!(u8, u16, u32, u64, usize);
+
+/// Returns true if the values differ by at least `min`.
+///
+/// ```rust
+/// use dbg_if::filter::delta_ne;
+/// assert!(!delta_ne(100u32, 199, 100));
+/// assert!(delta_ne(100u32, 200, 100));
+/// assert!(delta_ne(i8::MIN, i8::MAX, 100));
+/// ```
+pub fn delta_ne<T: IntDelta>(last_value: T, new_value: T, min: T) -> bool {
+    last_value.delta(new_value) >= min.magnitude()
+}
+
+/// Returns true if `new_value` differs from `last_value` by at least `percent`
+/// percent of `last_value`. Any change from zero counts.
+///
+/// ```rust
+/// use dbg_if::filter::percent_ne;
+/// assert!(!percent_ne(200u32, 209, 5));
+/// assert!(percent_ne(200u32, 210, 5));
+/// assert!(percent_ne(-200i32, -190, 5));
+/// assert!(percent_ne(0u32, 1, 5));
+/// ```
+pub fn percent_ne<T: IntDelta>(last_value: T, new_value: T, percent: u32) -> bool {
+    let delta = last_value.delta(new_value);
+    delta != 0 && delta.saturating_mul(100) >= last_value.magnitude() * u128::from(percent)
+}
+
+/// Snapshot state for [`dbg_if_elems_ne!`](crate::dbg_if_elems_ne!) and
+/// [`was_elems_ne!`](crate::was_elems_ne!).
+#[cfg(feature = "alloc")]
+pub struct ElemsNe {
+    snapshot: Lock<Option<Box<dyn Any + Send>>>,
+}
+
+#[cfg(feature = "alloc")]
+impl ElemsNe {
+    /// Create an empty snapshot.
+    pub const fn new() -> Self {
+        ElemsNe {
+            snapshot: Lock::new(None),
+        }
+    }
+
+    /// Compares `values` element-wise with the snapshot using `ne(old, new)`
+    /// and stores them as the new snapshot.
+    ///
+    /// Returns `None` if there was no snapshot of this type, otherwise the
+    /// elements that changed, were added, or were removed.
+    pub fn diff<T, N>(&self, values: &[T], ne: N) -> Option<Vec<ElemChange<T>>>
+    where
+        T: Clone + Send + 'static,
+        N: Fn(T, T) -> bool,
+    {
+        let mut snapshot = self.snapshot.lock();
+        let Some(last) = snapshot.as_mut().and_then(|s| s.downcast_mut::<Vec<T>>()) else {
+            *snapshot = Some(Box::new(values.to_vec()));
+            return None;
+        };
+        let mut changes = Vec::new();
+        for index in 0..last.len().max(values.len()) {
+            let old = last.get(index);
+            let new = values.get(index);
+            let changed = match (old, new) {
+                (Some(old), Some(new)) => ne(old.clone(), new.clone()),
+                _ => true,
+            };
+            if changed {
+                changes.push(ElemChange {
+                    index,
+                    old: old.cloned(),
+                    new: new.cloned(),
+                });
+            }
+        }
+        last.clear();
+        last.extend_from_slice(values);
+        Some(changes)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Default for ElemsNe {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl fmt::Debug for ElemsNe {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ElemsNe").finish_non_exhaustive()
+    }
+}
+
+/// An element that changed between snapshots.
+///
+/// It displays as `[index]: old -> new` where a missing element is `(none)`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ElemChange<T> {
+    /// The index of the element.
+    pub index: usize,
+    /// The element in the last snapshot if any.
+    pub old: Option<T>,
+    /// The element now if any.
+    pub new: Option<T>,
+}
+
+impl<T: fmt::Debug> fmt::Display for ElemChange<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}]: ", self.index)?;
+        match &self.old {
+            Some(old) => write!(f, "{:?}", old)?,
+            None => f.write_str("(none)")?,
+        }
+        f.write_str(" -> ")?;
+        match &self.new {
+            Some(new) => write!(f, "{:?}", new),
+            None => f.write_str("(none)"),
+        }
+    }
+}
+
+/// An atomic cell that [`IfNe`] can store values in.
+pub trait Atomic {
+    /// The value stored.
+    type Value: Copy;
+
+    /// Fetches the value and applies a function to it that returns an optional
+    /// new value.
+    fn fetch_update<F>(&self, f: F) -> Result<Self::Value, Self::Value>
+    where
+        F: FnMut(Self::Value) -> Option<Self::Value>;
+
+    /// Returns true if the values are not equal. This is the comparison used
+    /// by `IfNe` when no `ne` function is given.
+    fn value_ne(last_value: Self::Value, new_value: Self::Value) -> bool
+    where
+        Self::Value: PartialEq,
+    {
+        last_value != new_value
+    }
+}
+
+macro_rules! impl_atomic {
+    ($($atomic:ty: $type:ty = $zero:expr $(; $value_ne:path)?),+ $(,)?) => {
+        $(
+            impl Atomic for $atomic {
+                type Value = $type;
+
+                fn fetch_update<F>(&self, f: F) -> Result<$type, $type>
+                where
+                    F: FnMut($type) -> Option<$type>,
+                {
+                    <$atomic>::fetch_update(self, Ordering::SeqCst, Ordering::SeqCst, f)
+                }
+
+                $(
+                    fn value_ne(last_value: $type, new_value: $type) -> bool {
+                        $value_ne(last_value, new_value, |a, b| a != b)
+                    }
+                )?
+            }
+
+            impl IfNe<$atomic> {
+                /// Create a filter.
+                pub const fn new() -> Self {
+                    IfNe {
+                        first: AtomicBool::new(true),
+                        last: <$atomic>::new($zero),
+                    }
+                }
+            }
+
+            impl Default for IfNe<$atomic> {
+                fn default() -> Self {
+                    Self::new()
+                }
+            }
+        )+
+    };
+}
+
+impl_atomic! {
+    AtomicU8: u8 = 0,
+    AtomicU16: u16 = 0,
+    AtomicU32: u32 = 0,
+    AtomicUsize: usize = 0,
+    AtomicI8: i8 = 0,
+    AtomicI16: i16 = 0,
+    AtomicI32: i32 = 0,
+    AtomicIsize: isize = 0,
+}
+
+#[cfg(target_has_atomic = "64")]
+impl_atomic! {
+    AtomicU64: u64 = 0,
+    AtomicI64: i64 = 0,
+}
+
+/// The cell for `u64` values: atomic if the target has 64-bit atomics, such
+/// as x86_64 and aarch64, and locked otherwise, such as on thumbv7em.
+#[doc(hidden)]
+#[cfg(target_has_atomic = "64")]
+pub type U64Cell = AtomicU64;
+#[doc(hidden)]
+#[cfg(not(target_has_atomic = "64"))]
+pub type U64Cell = Locked<u64>;
+
+/// The cell for `i64` values, like [`U64Cell`].
+#[doc(hidden)]
+#[cfg(target_has_atomic = "64")]
+pub type I64Cell = AtomicI64;
+#[doc(hidden)]
+#[cfg(not(target_has_atomic = "64"))]
+pub type I64Cell = Locked<i64>;
+
+// NaN is never equal to itself, so without `nan_ne` a NaN would print every
+// time.
+#[cfg(feature = "float")]
+impl_atomic! {
+    atomic_float::AtomicF32: f32 = 0.0; nan_ne,
+    atomic_float::AtomicF64: f64 = 0.0; nan_ne,
+}
+
+/// A lock-backed cell for [`IfNe`] to store values that have no atomic type,
+/// like arrays, tuples, or vectors.
+///
+/// The `IfNe(type)` filter uses it for any `type` other than a primitive
+/// integer or float.
+#[derive(Debug)]
+pub struct Locked<T> {
+    value: Lock<Option<T>>,
+}
+
+impl<T> Locked<T> {
+    /// Create an empty cell.
+    pub const fn new() -> Self {
+        Locked {
+            value: Lock::new(None),
+        }
+    }
+}
+
+impl<T> Default for Locked<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Copy + Default> Atomic for Locked<T> {
+    type Value = T;
+
+    fn fetch_update<F>(&self, mut f: F) -> Result<T, T>
+    where
+        F: FnMut(T) -> Option<T>,
+    {
+        let mut value = self.value.lock();
+        let last = value.unwrap_or_default();
+        match f(last) {
+            Some(new) => {
+                *value = Some(new);
+                Ok(last)
+            }
+            None => Err(last),
+        }
+    }
+}
+
+impl<T: Copy + Default> IfNe<Locked<T>> {
+    /// Create a filter.
+    pub const fn new() -> Self {
+        IfNe {
+            first: AtomicBool::new(true),
+            last: Locked::new(),
+        }
+    }
+}
+
+impl<T: Copy + Default> Default for IfNe<Locked<T>> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,116 @@
+//! Support for approximate comparisons of compound float types.
+//!
+//! The [`approx`] crate implements its traits for scalars and slices but not for
+//! arrays or tuples. The `*_ne_args!` macros wrap their arguments in [`Approx`]
+//! and call `approx_value()`, which resolves to [`ApproxElems`] for arrays and
+//! tuples and falls back to [`ApproxSelf`] for everything else, e.g., `f32` or
+//! a vector type that implements [`approx::AbsDiffEq`] itself.
+use approx::{AbsDiffEq, RelativeEq, UlpsEq};
+
+/// Wraps a value to select how it is compared.
+#[derive(Debug, Clone, Copy)]
+pub struct Approx<T>(pub T);
+
+/// Compares arrays and tuples element-wise.
+pub trait ApproxElems {
+    /// The comparable value.
+    type Output;
+    /// Returns the comparable value.
+    fn approx_value(&self) -> Self::Output;
+}
+
+/// Compares values that implement the [`approx`] traits themselves.
+pub trait ApproxSelf {
+    /// The comparable value.
+    type Output;
+    /// Returns the comparable value.
+    fn approx_value(&self) -> Self::Output;
+}
+
+impl<T: Copy> ApproxSelf for &Approx<T> {
+    type Output = T;
+    fn approx_value(&self) -> T {
+        self.0
+    }
+}
+
+impl<E: Copy, const N: usize> ApproxElems for Approx<[E; N]> {
+    type Output = ApproxArray<E, N>;
+    fn approx_value(&self) -> Self::Output {
+        ApproxArray(self.0)
+    }
+}
+
+macro_rules! impl_approx_tuple {
+    ($($n:literal: ($($i:tt),+)),+ $(,)?) => {
+        $(
+            impl<E: Copy> ApproxElems for Approx<($(impl_approx_tuple!(@elem $i),)+)> {
+                type Output = ApproxArray<E, $n>;
+                fn approx_value(&self) -> Self::Output {
+                    ApproxArray([$((self.0).$i),+])
+                }
+            }
+        )+
+    };
+    (@elem $i:tt) => { E };
+}
+
+impl_approx_tuple! {
+    1: (0),
+    2: (0, 1),
+    3: (0, 1, 2),
+    4: (0, 1, 2, 3),
+}
+
+/// An array of values compared element-wise.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ApproxArray<E, const N: usize>(pub [E; N]);
+
+impl<E, const N: usize> AbsDiffEq for ApproxArray<E, N>
+where
+    E: AbsDiffEq,
+    E::Epsilon: Clone,
+{
+    type Epsilon = E::Epsilon;
+
+    fn default_epsilon() -> Self::Epsilon {
+        E::default_epsilon()
+    }
+
+    fn abs_diff_eq(&self, other: &Self, epsilon: Self::Epsilon) -> bool {
+        self.0[..].abs_diff_eq(&other.0[..], epsilon)
+    }
+}
+
+impl<E, const N: usize> RelativeEq for ApproxArray<E, N>
+where
+    E: RelativeEq,
+    E::Epsilon: Clone,
+{
+    fn default_max_relative() -> Self::Epsilon {
+        E::default_max_relative()
+    }
+
+    fn relative_eq(
+        &self,
+        other: &Self,
+        epsilon: Self::Epsilon,
+        max_relative: Self::Epsilon,
+    ) -> bool {
+        self.0[..].relative_eq(&other.0[..], epsilon, max_relative)
+    }
+}
+
+impl<E, const N: usize> UlpsEq for ApproxArray<E, N>
+where
+    E: UlpsEq,
+    E::Epsilon: Clone,
+{
+    fn default_max_ulps() -> u32 {
+        E::default_max_ulps()
+    }
+
+    fn ulps_eq(&self, other: &Self, epsilon: Self::Epsilon, max_ulps: u32) -> bool {
+        self.0[..].ulps_eq(&other.0[..], epsilon, max_ulps)
+    }
+}
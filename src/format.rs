@@ -0,0 +1,261 @@
+//! How the printing macros format values.
+//!
+//! Values are pretty printed with `{:#?}` like [`std::dbg!`] by default. Use
+//! [`set_format`] to choose compact `{:?}` or `{}` instead, and
+//! [`set_max_len`] to truncate long values with an ellipsis.
+//!
+//! ```rust
+//! use dbg_if::{dbg_once, format::{set_format, set_max_len, Format}};
+//!
+//! set_format(Format::Compact);
+//! set_max_len(Some(8));
+//! dbg_once!((1, 2)); // Outputs: [src/format.rs:9:1] (1, 2) = (1, 2)
+//! dbg_once!("a long string"); // Outputs: [src/format.rs:10:1] "a long string" = "a long …
+//! ```
+//!
+//! [`dbg_if!`](crate::dbg_if) and [`dbg_with!`](crate::dbg_with) also accept
+//! a format after a `;` for a call site.
+//!
+//! ```rust
+//! use dbg_if::dbg_if;
+//! dbg_if!((1, 2); Compact); // Outputs: [src/format.rs:3:1] (1, 2) = (1, 2)
+//! dbg_if!("hello", Once; Display, max_len = 3); // Outputs: [src/format.rs:4:1] "hello" = hel…
+//! ```
+use core::{
+    fmt,
+    sync::atomic::{AtomicU8, AtomicUsize, Ordering},
+};
+
+/// How to format values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Format {
+    /// Multiple lines with `{:#?}` like [`std::dbg!`].
+    #[default]
+    Pretty,
+    /// One line with `{:?}`.
+    Compact,
+    /// One line with `{}`, or `{:?}` if the value does not implement
+    /// [`Display`](fmt::Display).
+    Display,
+}
+
+impl Format {
+    const fn from_u8(n: u8) -> Self {
+        match n {
+            1 => Format::Compact,
+            2 => Format::Display,
+            _ => Format::Pretty,
+        }
+    }
+}
+
+static FORMAT: AtomicU8 = AtomicU8::new(Format::Pretty as u8);
+static MAX_LEN: AtomicUsize = AtomicUsize::new(0);
+
+/// Sets how values are formatted unless a call site says otherwise.
+pub fn set_format(format: Format) {
+    FORMAT.store(format as u8, Ordering::Relaxed);
+}
+
+/// Sets the number of characters after which values are truncated with an
+/// ellipsis unless a call site says otherwise. `None` does not truncate.
+pub fn set_max_len(max_len: Option<usize>) {
+    MAX_LEN.store(max_len.unwrap_or(0), Ordering::Relaxed);
+}
+
+/// The format of a call site; `None` defers to the global setting.
+#[doc(hidden)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Options {
+    format: Option<Format>,
+    max_len: Option<usize>,
+}
+
+impl Options {
+    /// Defers to the global settings.
+    pub const fn new() -> Self {
+        Options {
+            format: None,
+            max_len: None,
+        }
+    }
+
+    /// Overrides [`set_format`].
+    pub const fn format(mut self, format: Format) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    /// Overrides [`set_max_len`]; zero does not truncate.
+    pub const fn max_len(mut self, max_len: usize) -> Self {
+        self.max_len = Some(max_len);
+        self
+    }
+
+    fn resolved_format(&self) -> Format {
+        self.format
+            .unwrap_or_else(|| Format::from_u8(FORMAT.load(Ordering::Relaxed)))
+    }
+
+    /// Returns these options with compact instead of pretty formatting.
+    #[cfg(feature = "std")]
+    pub(crate) fn one_line(self) -> Self {
+        if self.is_pretty() {
+            self.format(Format::Compact)
+        } else {
+            self
+        }
+    }
+
+    /// Returns true if values are formatted on multiple lines.
+    #[cfg(feature = "std")]
+    pub(crate) fn is_pretty(&self) -> bool {
+        self.resolved_format() == Format::Pretty
+    }
+
+    /// Formats `value` with these options.
+    pub(crate) fn apply<'a>(&self, value: Value<'a>) -> impl fmt::Display + 'a {
+        Truncate {
+            value: Formatted {
+                value,
+                format: self.resolved_format(),
+            },
+            max_len: self.max_len.unwrap_or_else(|| MAX_LEN.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+/// A value to print that may also implement [`Display`](fmt::Display).
+#[doc(hidden)]
+#[derive(Clone, Copy)]
+pub struct Value<'a> {
+    debug: &'a dyn fmt::Debug,
+    display: Option<&'a dyn fmt::Display>,
+}
+
+/// Wraps a value to find out whether it implements [`Display`](fmt::Display).
+#[doc(hidden)]
+pub struct Wrap<'a, T>(pub &'a T);
+
+/// Selected for values that implement [`Display`](fmt::Display).
+#[doc(hidden)]
+pub trait ViaDisplay<'a> {
+    /// Returns the value to print.
+    fn dbg_value(&self) -> Value<'a>;
+}
+
+/// Selected for values that only implement [`Debug`](fmt::Debug).
+#[doc(hidden)]
+pub trait ViaDebug<'a> {
+    /// Returns the value to print.
+    fn dbg_value(&self) -> Value<'a>;
+}
+
+impl<'a, T: fmt::Debug + fmt::Display> ViaDisplay<'a> for Wrap<'a, T> {
+    fn dbg_value(&self) -> Value<'a> {
+        Value {
+            debug: self.0,
+            display: Some(self.0),
+        }
+    }
+}
+
+impl<'a, T: fmt::Debug> ViaDebug<'a> for &Wrap<'a, T> {
+    fn dbg_value(&self) -> Value<'a> {
+        Value {
+            debug: self.0,
+            display: None,
+        }
+    }
+}
+
+struct Formatted<'a> {
+    value: Value<'a>,
+    format: Format,
+}
+
+impl fmt::Display for Formatted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.format, self.value.display) {
+            (Format::Pretty, _) => write!(f, "{:#?}", self.value.debug),
+            (Format::Display, Some(display)) => write!(f, "{}", display),
+            _ => write!(f, "{:?}", self.value.debug),
+        }
+    }
+}
+
+/// Writes at most `max_len` characters followed by an ellipsis if there were
+/// more. A `max_len` of zero writes everything.
+struct Truncate<D> {
+    value: D,
+    max_len: usize,
+}
+
+impl<D: fmt::Display> fmt::Display for Truncate<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.max_len == 0 {
+            return write!(f, "{}", self.value);
+        }
+        let mut limit = Limit {
+            f,
+            left: self.max_len,
+            cut: false,
+        };
+        let result = fmt::write(&mut limit, format_args!("{}", self.value));
+        if limit.cut {
+            f.write_str("…")
+        } else {
+            result
+        }
+    }
+}
+
+struct Limit<'a, 'b> {
+    f: &'a mut fmt::Formatter<'b>,
+    left: usize,
+    cut: bool,
+}
+
+impl fmt::Write for Limit<'_, '_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        match s.char_indices().nth(self.left) {
+            None => {
+                self.left -= s.chars().count();
+                self.f.write_str(s)
+            }
+            Some((end, _)) => {
+                self.f.write_str(&s[..end])?;
+                self.left = 0;
+                self.cut = true;
+                // Stop formatting the rest of the value.
+                Err(fmt::Error)
+            }
+        }
+    }
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! value {
+    ($val:expr) => {{
+        use $crate::format::{ViaDebug as _, ViaDisplay as _};
+        (&$crate::format::Wrap($val)).dbg_value()
+    }};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! format_options {
+    (@ [$($options:tt)*]) => {
+        $($options)*
+    };
+    (@ [$($options:tt)*] max_len = $max_len:expr $(, $($rest:tt)*)?) => {
+        $crate::format_options!(@ [$($options)*.max_len($max_len)] $($($rest)*)?)
+    };
+    (@ [$($options:tt)*] $format:ident $(, $($rest:tt)*)?) => {
+        $crate::format_options!(@ [$($options)*.format($crate::format::Format::$format)] $($($rest)*)?)
+    };
+    ($($format:tt)*) => {
+        $crate::format_options!(@ [$crate::format::Options::new()] $($format)*)
+    };
+}
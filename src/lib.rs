@@ -1,11 +1,95 @@
 #![doc(html_root_url = "https://docs.rs/dbg_if/0.1.0")]
+// The README links to items behind features; without them, link to docs.rs.
+#![cfg_attr(
+    not(feature = "alloc"),
+    doc = concat!(
+        "[`dbg_if_elems_ne!`]: https://docs.rs/dbg_if/latest/dbg_if/macro.dbg_if_elems_ne.html\n",
+        "[`was_elems_ne!`]: https://docs.rs/dbg_if/latest/dbg_if/macro.was_elems_ne.html",
+    )
+)]
+#![cfg_attr(
+    not(feature = "std"),
+    doc = concat!(
+        "[`capture()`]: https://docs.rs/dbg_if/latest/dbg_if/fn.capture.html\n",
+        "[`DbgScope`]: https://docs.rs/dbg_if/latest/dbg_if/struct.DbgScope.html\n",
+        "[`dump::install_panic_hook`]: https://docs.rs/dbg_if/latest/dbg_if/dump/fn.install_panic_hook.html\n",
+        "[`recent::flight_recorder`]: https://docs.rs/dbg_if/latest/dbg_if/recent/fn.flight_recorder.html\n",
+        "[`dump_recent`]: https://docs.rs/dbg_if/latest/dbg_if/fn.dump_recent.html\n",
+        "[`watch::start`]: https://docs.rs/dbg_if/latest/dbg_if/watch/fn.start.html\n",
+        "[`file::open`]: https://docs.rs/dbg_if/latest/dbg_if/file/fn.open.html\n",
+        "[`background::start`]: https://docs.rs/dbg_if/latest/dbg_if/background/fn.start.html",
+    )
+)]
+#![cfg_attr(
+    not(feature = "color"),
+    doc = "[`color::set_color`]: https://docs.rs/dbg_if/latest/dbg_if/color/fn.set_color.html"
+)]
+#![cfg_attr(
+    not(feature = "tui"),
+    doc = "[`tui`]: https://docs.rs/dbg_if/latest/dbg_if/tui/index.html"
+)]
+#![cfg_attr(
+    not(feature = "float"),
+    doc = concat!(
+        "[`abs_diff_ne_args!`]: https://docs.rs/dbg_if/latest/dbg_if/macro.abs_diff_ne_args.html\n",
+        "[`relative_ne_args!`]: https://docs.rs/dbg_if/latest/dbg_if/macro.relative_ne_args.html\n",
+        "[`ulps_ne_args!`]: https://docs.rs/dbg_if/latest/dbg_if/macro.ulps_ne_args.html\n",
+        "[`approx`]: https://docs.rs/approx",
+    )
+)]
 #![doc = include_str!("../README.md")]
 #![forbid(missing_docs)]
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(all(doc, not(feature = "std")))]
+extern crate std;
+
+pub mod filter;
+pub use filter::DbgFilter;
+#[cfg(feature = "std")]
+pub mod background;
+#[cfg(feature = "std")]
+pub mod capture;
+#[cfg(feature = "std")]
+pub use capture::{capture, DbgRecord};
+#[cfg(feature = "color")]
+pub mod color;
+#[cfg(feature = "std")]
+pub mod dump;
+#[cfg(feature = "std")]
+pub mod file;
+pub mod format;
+mod lock;
+pub mod prefix;
+#[cfg(feature = "std")]
+pub mod recent;
+#[cfg(feature = "std")]
+pub use recent::dump_recent;
+pub mod report;
+pub use report::report;
+pub mod scope;
+#[cfg(all(feature = "signal", unix))]
+pub mod signal;
+#[cfg(feature = "std")]
+pub use scope::DbgScope;
+pub use prefix::set_tick;
+#[cfg(feature = "tui")]
+pub mod tui;
+#[cfg(feature = "std")]
+pub mod watch;
+pub mod write;
+
+#[doc(hidden)]
+#[cfg(feature = "float")]
+pub mod float;
 
 /// A kind of drop in replacement for [dbg](std::dbg)!
 ///
 /// This is a facade for the rest of the macros that key off the second argument
-/// if present.
+/// if present. The second argument may also combine [filters](filter) with `&`
+/// and `|`.
 ///
 /// ```rust
 /// use dbg_if::dbg_if as dbg;
@@ -14,7 +98,8 @@
 ///     dbg!(x + 1);
 ///     dbg!(x + 2, Once);
 ///     dbg!(x + 3, IfNe, u8);
-///     dbg!(x + 4, IfHashNe)
+///     dbg!(x + 4, IfHashNe);
+///     dbg!(x + 5, When(|v| *v > 5) & Once)
 /// }
 ///
 /// x = f(x);
@@ -28,36 +113,39 @@
 /// [src/lib.rs:11:9] x + 2 = 2
 /// [src/lib.rs:12:9] x + 3 = 3
 /// [src/lib.rs:13:9] x + 4 = 4
-/// [src/lib.rs:10:9] x + 1 = 1 // Only the first statement will print again.
+/// [src/lib.rs:10:9] x + 1 = 6
+/// [src/lib.rs:12:9] x + 3 = 8 // No `x + 2`: `Once` does not print again.
+/// [src/lib.rs:13:9] x + 4 = 9
+/// [src/lib.rs:14:9] x + 5 = 10 // `When` printed once its predicate held.
 /// ````
 #[macro_export]
 macro_rules! dbg_if {
-    ($val:expr) => {
-        ::std::dbg!($val)
-    };
-
-    ($val:expr, Every) => {
-        ::std::dbg!($val)
+    ($val:expr $(,)?) => {
+        $crate::dbg_with!($val, Every)
     };
 
-    ($val:expr, Once) => {
-        ::dbg_if::dbg_once!($val)
+    ($val:expr; $($format:tt)+) => {
+        $crate::dbg_with!($val, Every; $($format)+)
     };
 
     ($val:expr, IfNe, $type:tt) => {
-        ::dbg_if::dbg_if_ne!($val, $type)
+        $crate::dbg_if_ne!($val, $type)
     };
 
     ($val:expr, IfNe, $type:tt, $ne:expr) => {
-        ::dbg_if::dbg_if_ne!($val, $type, $ne)
+        $crate::dbg_if_ne!($val, $type, $ne)
     };
 
-    ($val:expr, IfHashNe) => {
-        ::dbg_if::dbg_if_hash_ne!($val)
+    ($val:expr, IfHashNe, $ne:expr) => {
+        $crate::dbg_if_hash_ne!($val, $ne)
     };
 
-    ($val:expr, IfHashNe, $ne:expr) => {
-        ::dbg_if::dbg_if_hash_ne!($val, $ne)
+    ($val:expr, When($pred:expr), $($mode:tt)+) => {
+        $crate::dbg_when!($val, $pred, $($mode)+)
+    };
+
+    ($val:expr, $($filter:tt)+) => {
+        $crate::dbg_with!($val, $($filter)+)
     };
 }
 
@@ -83,14 +171,16 @@ macro_rules! dbg_if {
 /// ```
 #[macro_export]
 macro_rules! once {
-    () => {{
-        {
+    () => {
+        $crate::enabled!({
             use ::core::sync::atomic::{AtomicBool, Ordering};
             static FIRST: AtomicBool = AtomicBool::new(true);
-            let first = FIRST.swap(false, Ordering::Relaxed);
+            let first = $crate::scope::local(&FIRST, || AtomicBool::new(true)).swap(false, Ordering::Relaxed);
             first
-        }
-    }};
+        } else {
+            false
+        })
+    };
 }
 
 /// Calls [`std::dbg`] exactly once per call site.
@@ -101,17 +191,52 @@ macro_rules! once {
 ///     dbg_once!(i); // Outputs: [src/lib.rs:9:9] x = 0
 /// }
 /// ```
+///
+/// Like [`std::dbg`], each argument is evaluated exactly once and the macro
+/// returns the value, or a tuple of values if given more than one.
+///
+/// ```rust
+/// use dbg_if::dbg_once;
+/// fn f(a: u8, b: u8) -> (u8, u8) {
+///     dbg_once!(a, b)
+/// }
+/// assert_eq!(f(1, 2), (1, 2)); // Outputs: [src/lib.rs:9:9] a = 1
+///                              //          [src/lib.rs:9:9] b = 2
+/// assert_eq!(f(3, 4), (3, 4)); // No output.
+/// ```
 #[macro_export]
 macro_rules! dbg_once {
-    ($($arg:tt)*) => {{
-        {
+    () => {
+        $crate::enabled! {
+            static SITE: $crate::report::Site = $crate::report::Site::new($crate::location!(), "");
+            SITE.hit();
             if $crate::once!() {
-                std::dbg!($($arg)+)
-            } else {
-                $($arg)+
+                $crate::write::dbg_location(&SITE);
             }
         }
-    }};
+    };
+    ($val:expr $(,)?) => {
+        match $val {
+            tmp => {
+                $crate::enabled! {
+                    static SITE: $crate::report::Site = $crate::report::Site::new($crate::location!(), ::core::stringify!($val));
+                    SITE.hit();
+                    SITE.observe($crate::value!(&tmp));
+                    if $crate::once!() {
+                        $crate::write::dbg_value(
+                            &SITE,
+                            $crate::value!(&tmp),
+                            $crate::format::Options::new(),
+                        );
+                    }
+                }
+                tmp
+            }
+        }
+    };
+    ($($val:expr),+ $(,)?) => {
+        ($($crate::dbg_once!($val)),+,)
+    };
 }
 
 /// Returns true if the given expression is not equal to what it was.
@@ -131,7 +256,7 @@ macro_rules! dbg_once {
 /// # Use a closure as third argument
 ///
 /// This macro accepts a third argument for a function or closure "not equal" or
-/// `ne` with this signature: `FnMut<T>(T, T) -> bool`.
+/// `ne` with this signature: `Fn(T, T) -> bool`.
 ///
 /// ```rust
 /// use dbg_if::was_ne;
@@ -148,22 +273,11 @@ macro_rules! dbg_once {
 #[macro_export]
 macro_rules! was_ne {
     ($val:expr, $type:tt, $ne:expr) => {
-        {
-            use ::core::{sync::atomic::{AtomicBool, Ordering}};
-            static FIRST: AtomicBool = AtomicBool::new(true);
-            let first = FIRST.swap(false, Ordering::Relaxed);
-            match $val {
-                tmp => {
-                    $crate::static_atomic!(VALUE: $type);
-                    let ne_fn = $ne;
-                    VALUE.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |v| (first || ne_fn(v, tmp)).then_some(tmp)).is_ok()
-                }
-            }
-        }
+        $crate::with!($val, IfNe($type, $ne))
     };
 
     ($val:expr, $type:tt $(,)?) => {
-        was_ne!($val, $type, |last_value, new_value| last_value != new_value)
+        $crate::with!($val, IfNe($type))
     };
 }
 
@@ -182,7 +296,7 @@ macro_rules! was_ne {
 /// # Use a closure as third argument
 ///
 /// This macro accepts a third argument for a function or closure "not equal" or
-/// `ne` with this signature: `FnMut<T>(T, T) -> bool`.
+/// `ne` with this signature: `Fn(T, T) -> bool`.
 ///
 /// ```rust
 /// use dbg_if::dbg_if_ne;
@@ -198,26 +312,11 @@ macro_rules! was_ne {
 #[macro_export]
 macro_rules! dbg_if_ne {
     ($val:expr, $type:tt, $ne:expr) => {
-        {
-            use ::core::{sync::atomic::{AtomicBool, Ordering}};
-            static FIRST: AtomicBool = AtomicBool::new(true);
-            let first = FIRST.swap(false, Ordering::Relaxed);
-            match $val {
-                tmp => {
-                    $crate::static_atomic!(VALUE: $type);
-                    let ne_fn = $ne;
-                    if VALUE.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |v| (first || ne_fn(v, tmp)).then_some(tmp)).is_ok() {
-                        ::std::eprintln!("[{}:{}:{}] {} = {:#?}",
-                                         ::std::file!(), ::std::line!(), ::std::column!(), ::std::stringify!($val), &tmp);
-                    }
-                    tmp
-                }
-            }
-        }
+        $crate::dbg_with!($val, IfNe($type, $ne))
     };
 
     ($val:expr, $type:tt $(,)?) => {
-        $crate::dbg_if_ne!($val, $type, |last_value, new_value| last_value != new_value)
+        $crate::dbg_with!($val, IfNe($type))
     };
 }
 
@@ -237,24 +336,7 @@ macro_rules! dbg_if_ne {
 #[macro_export]
 macro_rules! was_hash_ne {
     ($val:expr $(,)?) => {
-        match $val {
-            tmp => {
-                use ::core::{
-                    hash::{Hash, Hasher},
-                    sync::atomic::{AtomicU64, Ordering},
-                };
-                static HASH: AtomicU64 = AtomicU64::new(0);
-                let mut s = ::std::hash::DefaultHasher::new();
-                tmp.hash(&mut s);
-                let current_hash = s.finish();
-                let changed = HASH
-                    .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |h| {
-                        (h != current_hash).then_some(current_hash)
-                    })
-                    .is_ok();
-                changed
-            }
-        }
+        $crate::with!($val, IfHashNe)
     };
 }
 
@@ -274,25 +356,303 @@ macro_rules! was_hash_ne {
 #[macro_export]
 macro_rules! dbg_if_hash_ne {
     ($val:expr $(,)?) => {
+        $crate::dbg_with!($val, IfHashNe)
+    };
+    ($($val:expr),+ $(,)?) => {
+        ($($crate::dbg_if_hash_ne!($val)),+,)
+    };
+}
+
+/// Returns true if any element of the given slice is not equal to what it was.
+///
+/// The argument may be anything that is [`AsRef<[T]>`](AsRef), like a `Vec`,
+/// array, or slice. A snapshot of its elements is kept per call site, which
+/// requires `T: Clone + Send + 'static` and the "alloc" feature.
+///
+/// ```rust
+/// use dbg_if::was_elems_ne;
+/// fn f(v: &[u8]) -> bool {
+///     was_elems_ne!(v)
+/// }
+/// assert!(f(&[1, 2, 3]));
+/// assert!(!f(&[1, 2, 3]));
+/// assert!(f(&[1, 5, 3]));
+/// assert!(f(&[1, 5]));
+/// ```
+///
+/// # Use a closure as second argument
+///
+/// Like [`was_ne!`], this macro accepts a function or closure "not equal" or
+/// `ne` with this signature: `Fn(T, T) -> bool`, which is applied to each
+/// element.
+#[cfg(feature = "alloc")]
+#[macro_export]
+macro_rules! was_elems_ne {
+    ($val:expr, $ne:expr $(,)?) => {
+        match $val {
+            tmp => $crate::enabled!({
+                static ELEMS: $crate::filter::ElemsNe = $crate::filter::ElemsNe::new();
+                $crate::scope::local(&ELEMS, $crate::filter::ElemsNe::new)
+                    .diff(::core::convert::AsRef::<[_]>::as_ref(&tmp), $ne)
+                    .map_or(true, |changes| !changes.is_empty())
+            } else {
+                false
+            })
+        }
+    };
+
+    ($val:expr $(,)?) => {
+        $crate::was_elems_ne!($val, |last_value, new_value| last_value != new_value)
+    };
+}
+
+/// Calls [`std::dbg`] the first time, then only reports the elements of the
+/// argument that are not equal to what they were.
+///
+/// ```rust
+/// use dbg_if::dbg_if_elems_ne;
+/// fn f(v: &Vec<f32>) {
+///     dbg_if_elems_ne!(v);
+/// }
+/// let mut v = vec![1.0, 2.0, 3.0, 4.0];
+/// f(&v); // Outputs: [src/lib.rs:58:9] v = [1.0, 2.0, 3.0, 4.0]
+/// f(&v); // No output.
+/// v[3] = 1.5;
+/// f(&v); // Outputs: [src/lib.rs:58:9] v[3]: 4.0 -> 1.5
+/// v.push(5.0);
+/// f(&v); // Outputs: [src/lib.rs:58:9] v[4]: (none) -> 5.0
+/// ```
+///
+/// # Use a closure as second argument
+///
+/// Like [`dbg_if_ne!`], this macro accepts a function or closure "not equal"
+/// or `ne` with this signature: `Fn(T, T) -> bool`, which is applied to each
+/// element.
+///
+/// ```rust
+/// use dbg_if::dbg_if_elems_ne;
+/// fn f(v: &[i32]) {
+///     dbg_if_elems_ne!(v, |a: i32, b: i32| (a - b).abs() >= 10);
+/// }
+/// f(&[0, 0]);   // Outputs: [src/lib.rs:58:9] v = [0, 0]
+/// f(&[5, 10]);  // Outputs: [src/lib.rs:58:9] v[1]: 0 -> 10
+/// ```
+#[cfg(feature = "alloc")]
+#[macro_export]
+macro_rules! dbg_if_elems_ne {
+    ($val:expr, $ne:expr $(,)?) => {
         match $val {
             tmp => {
+                $crate::enabled! {
+                    static SITE: $crate::report::Site = $crate::report::Site::new($crate::location!(), ::core::stringify!($val));
+                    static ELEMS: $crate::filter::ElemsNe = $crate::filter::ElemsNe::new();
+                    SITE.hit();
+                    SITE.observe($crate::value!(&tmp));
+                    match $crate::scope::local(&ELEMS, $crate::filter::ElemsNe::new).diff(::core::convert::AsRef::<[_]>::as_ref(&tmp), $ne) {
+                        None => {
+                            $crate::write::dbg_value(
+                                &SITE,
+                                $crate::value!(&tmp),
+                                $crate::format::Options::new(),
+                            );
+                        }
+                        Some(changes) => {
+                            for change in changes {
+                                $crate::write::dbg_change(&SITE, &change);
+                            }
+                        }
+                    }
+                }
+                tmp
+            }
+        }
+    };
+
+    ($val:expr $(,)?) => {
+        $crate::dbg_if_elems_ne!($val, |last_value, new_value| last_value != new_value)
+    };
+}
+
+/// Returns true if the predicate holds for the given expression.
+///
+/// The predicate is a function or closure with this signature:
+/// `Fn(&T) -> bool`.
+///
+/// ```rust
+/// use dbg_if::when;
+/// fn f(x: f32) -> bool {
+///     when!(x, |v| v.is_nan())
+/// }
+/// assert!(!f(1.0));
+/// assert!(f(f32::NAN));
+/// ```
+///
+/// # Combine with another mode
+///
+/// An optional trailing mode of `Once`, `IfNe, type`, or `IfHashNe` is only
+/// consulted when the predicate holds.
+///
+/// ```rust
+/// use dbg_if::when;
+/// fn f(x: u8) -> bool {
+///     when!(x, |v| *v > 100, Once)
+/// }
+/// assert!(!f(1));
+/// assert!(f(101));
+/// assert!(!f(102));
+/// ```
+#[macro_export]
+macro_rules! when {
+    ($val:expr, $pred:expr $(,)?) => {
+        $crate::with!($val, When($pred))
+    };
+    ($val:expr, $pred:expr, IfNe, $type:tt $(, $ne:expr)? $(,)?) => {
+        $crate::with!($val, When($pred) & IfNe($type $(, $ne)?))
+    };
+    ($val:expr, $pred:expr, $mode:ident $(,)?) => {
+        $crate::with!($val, When($pred) & $mode)
+    };
+}
 
-                use ::core::{hash::{Hash, Hasher}, sync::atomic::{AtomicU64, Ordering}};
-                static HASH: AtomicU64 = AtomicU64::new(0);
-                let mut s = ::std::hash::DefaultHasher::new();
-                tmp.hash(&mut s);
-                let current_hash = s.finish();
-                if HASH.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |h| (h != current_hash).then_some(current_hash)).is_ok() {
-                    ::std::eprintln!("[{}:{}:{}] {} = {:#?}",
-                                     ::std::file!(), ::std::line!(), ::std::column!(), ::std::stringify!($val), &tmp);
+/// Calls [`std::dbg`] if the predicate holds for the argument.
+///
+/// ```rust
+/// use dbg_if::dbg_when;
+/// for i in 0..10 {
+///     dbg_when!(i, |v| v % 4 == 0);
+/// }
+/// // Outputs: [src/lib.rs:58:9] i = 0
+/// // Outputs: [src/lib.rs:58:9] i = 4
+/// // Outputs: [src/lib.rs:58:9] i = 8
+/// ```
+///
+/// # Combine with another mode
+///
+/// Like [`when!`], it accepts a trailing mode of `Once`, `IfNe, type`, or
+/// `IfHashNe` that is only consulted when the predicate holds.
+///
+/// ```rust
+/// use dbg_if::dbg_when;
+/// for i in 0..200u8 {
+///     dbg_when!(i, |v| *v > 100, Once); // Outputs: [src/lib.rs:58:9] i = 101
+/// }
+/// ```
+#[macro_export]
+macro_rules! dbg_when {
+    ($val:expr, $pred:expr $(,)?) => {
+        $crate::dbg_with!($val, When($pred))
+    };
+    ($val:expr, $pred:expr, IfNe, $type:tt $(, $ne:expr)? $(,)?) => {
+        $crate::dbg_with!($val, When($pred) & IfNe($type $(, $ne)?))
+    };
+    ($val:expr, $pred:expr, $mode:ident $(,)?) => {
+        $crate::dbg_with!($val, When($pred) & $mode)
+    };
+}
+
+/// Returns true if the [filter](filter) would print the given expression.
+///
+/// The second argument is a filter expression as accepted by [`dbg_if!`],
+/// which includes user-defined types that implement [`DbgFilter`] and provide
+/// a `const fn new() -> Self`. Each filter is allocated as a static per call
+/// site.
+///
+/// ```rust
+/// use dbg_if::with;
+/// fn f(x: u8) -> bool {
+///     with!(x, IfNe(u8) & When(|v| *v > 1))
+/// }
+/// assert!(!f(1));
+/// assert!(f(2));
+/// assert!(!f(2));
+/// ```
+#[macro_export]
+macro_rules! with {
+    ($val:expr, $($filter:tt)+) => {
+        match $val {
+            tmp => $crate::enabled!({
+                $crate::DbgFilter::should_print(&$crate::filter_expr!(tmp; $($filter)+), &tmp)
+            } else {
+                false
+            })
+        }
+    };
+}
+
+/// Calls [`std::dbg`] if the [filter](filter) would print the argument.
+///
+/// ```rust
+/// use dbg_if::{dbg_with, DbgFilter};
+/// use std::sync::atomic::{AtomicBool, Ordering};
+///
+/// /// Prints when the sign changes.
+/// struct SignChange(AtomicBool);
+///
+/// impl SignChange {
+///     const fn new() -> Self {
+///         SignChange(AtomicBool::new(false))
+///     }
+/// }
+///
+/// impl DbgFilter<i32> for SignChange {
+///     fn should_print(&self, value: &i32) -> bool {
+///         let negative = *value < 0;
+///         self.0.swap(negative, Ordering::Relaxed) != negative
+///     }
+/// }
+///
+/// for x in [1, 2, -1, -5, 3] {
+///     dbg_with!(x, SignChange);
+/// }
+/// // Outputs: [src/lib.rs:58:9] x = -1
+/// // Outputs: [src/lib.rs:58:9] x = 3
+/// ```
+///
+/// # Format
+///
+/// After a `;`, the call site may choose `Pretty`, `Compact`, or `Display`
+/// formatting and a `max_len` instead of the [global format](mod@format).
+///
+/// ```rust
+/// use dbg_if::dbg_with;
+/// let s = "a long string";
+/// dbg_with!(s, Once; Display, max_len = 6); // Outputs: [src/lib.rs:58:9] s = a long…
+/// ```
+#[macro_export]
+macro_rules! dbg_with {
+    // Split the filter from the format at `;`.
+    (@split $val:expr, [$($filter:tt)*] ; $($format:tt)*) => {
+        $crate::dbg_with!(@print $val, [$($filter)*] [$($format)*])
+    };
+    (@split $val:expr, [$($filter:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::dbg_with!(@split $val, [$($filter)* $next] $($rest)*)
+    };
+    (@split $val:expr, [$($filter:tt)*]) => {
+        $crate::dbg_with!(@print $val, [$($filter)*] [])
+    };
+
+    (@print $val:expr, [$($filter:tt)+] [$($format:tt)*]) => {
+        match $val {
+            tmp => {
+                $crate::enabled! {
+                    static SITE: $crate::report::Site = $crate::report::Site::new($crate::location!(), ::core::stringify!($val));
+                    SITE.hit();
+                    SITE.observe($crate::value!(&tmp));
+                    if $crate::DbgFilter::should_print(&$crate::filter_expr!(tmp; $($filter)+), &tmp) {
+                        $crate::write::dbg_value(
+                            &SITE,
+                            $crate::value!(&tmp),
+                            $crate::format_options!($($format)*),
+                        );
+                    }
                 }
                 tmp
             }
         }
     };
-    ($($val:expr),+ $(,)?) => {
 
-   ($($crate::dbg_if_hash_ne!($val)),+,)
+    ($val:expr, $($filter:tt)+) => {
+        $crate::dbg_with!(@split $val, [] $($filter)+)
     };
 }
 
@@ -322,11 +682,27 @@ macro_rules! dbg_if_hash_ne {
 /// assert!(!f(2.0));
 /// assert!(f(2.1));
 /// ```
+///
+/// # Compound types
+///
+/// Arrays and tuples of up to four floats are compared element-wise, and any
+/// other type that implements [approx::AbsDiffEq] is compared as is. These are
+/// stored behind a mutex rather than an atomic.
+///
+/// ```rust
+/// use dbg_if::{was_ne, abs_diff_ne_args};
+/// fn f(position: [f32; 3]) -> bool {
+///     was_ne!(position, [f32; 3], abs_diff_ne_args!(epsilon = 0.5))
+/// }
+/// assert!(f([0.0, 0.0, 0.0]));
+/// assert!(!f([0.1, 0.2, 0.3]));
+/// assert!(f([0.1, 0.2, 1.0]));
+/// ```
 #[cfg(feature = "float")]
 #[macro_export]
 macro_rules! abs_diff_ne_args {
     ($($arg:tt)*) => {
-        |a, b| ::approx::abs_diff_ne!(a, b, $($arg)*)
+        |a, b| ::approx::abs_diff_ne!($crate::approx_value!(a), $crate::approx_value!(b), $($arg)*)
     };
 }
 
@@ -360,7 +736,7 @@ macro_rules! abs_diff_ne_args {
 #[macro_export]
 macro_rules! relative_ne_args {
     ($($arg:tt)*) => {
-        |a, b| ::approx::relative_ne!(a, b, $($arg)*)
+        |a, b| ::approx::relative_ne!($crate::approx_value!(a), $crate::approx_value!(b), $($arg)*)
     };
 }
 
@@ -394,70 +770,287 @@ macro_rules! relative_ne_args {
 #[macro_export]
 macro_rules! ulps_ne_args {
     ($($arg:tt)*) => {
-        |a, b| ::approx::ulps_ne!(a, b, $($arg)*)
+        |a, b| ::approx::ulps_ne!($crate::approx_value!(a), $crate::approx_value!(b), $($arg)*)
+    };
+}
+
+/// Returns a not equal closure with hysteresis appropriate for the third
+/// argument of [`was_ne`] or [`dbg_if_ne`].
+///
+/// A change is reported when the value rises above `high` or falls below `low`,
+/// but not again until it crosses the opposite threshold. Values in between
+/// never count as a change. This keeps noisy values near a threshold quiet.
+///
+/// ```rust
+/// use dbg_if::{was_ne, hysteresis_ne_args};
+/// fn f(x: u8) -> bool {
+///     was_ne!(x, u8, hysteresis_ne_args!(5, 10))
+/// }
+/// assert!(f(7));   // First value.
+/// assert!(!f(9));
+/// assert!(f(11));  // Rose above 10.
+/// assert!(!f(8));
+/// assert!(!f(12));
+/// assert!(f(4));   // Fell below 5.
+/// assert!(!f(6));
+/// assert!(!f(3));
+/// ```
+///
+/// The band state is kept per call site between calls.
+#[macro_export]
+macro_rules! hysteresis_ne_args {
+    ($low:expr, $high:expr $(,)?) => {{
+        static BAND: $crate::filter::Hysteresis = $crate::filter::Hysteresis::new();
+        move |last_value, new_value| {
+            $crate::scope::local(&BAND, $crate::filter::Hysteresis::new)
+                .crossed(last_value, new_value, $low, $high)
+        }
+    }};
+}
+
+/// Returns a not equal closure for integers appropriate for the third argument
+/// of [`was_ne`] or [`dbg_if_ne`].
+///
+/// A change is reported when the value differs from the last reported value by
+/// at least `min`.
+///
+/// ```rust
+/// use dbg_if::{was_ne, delta_ne_args};
+/// fn f(x: u32) -> bool {
+///     was_ne!(x, u32, delta_ne_args!(min = 100))
+/// }
+/// assert!(f(1000));
+/// assert!(!f(1050));
+/// assert!(!f(901));
+/// assert!(f(1100));
+/// ```
+#[macro_export]
+macro_rules! delta_ne_args {
+    (min = $min:expr $(,)?) => {
+        |last_value, new_value| $crate::filter::delta_ne(last_value, new_value, $min)
+    };
+}
+
+/// Returns a not equal closure for integers appropriate for the third argument
+/// of [`was_ne`] or [`dbg_if_ne`].
+///
+/// A change is reported when the value differs from the last reported value by
+/// at least the given percent of the last reported value.
+///
+/// ```rust
+/// use dbg_if::{was_ne, percent_ne_args};
+/// fn f(x: i64) -> bool {
+///     was_ne!(x, i64, percent_ne_args!(5))
+/// }
+/// assert!(f(1000));
+/// assert!(!f(1049));
+/// assert!(f(950));
+/// assert!(!f(910));
+/// ```
+#[macro_export]
+macro_rules! percent_ne_args {
+    ($percent:expr $(,)?) => {
+        |last_value, new_value| $crate::filter::percent_ne(last_value, new_value, $percent)
+    };
+}
+
+/// Returns a NaN-aware not equal closure appropriate for the third argument of
+/// [`was_ne`] or [`dbg_if_ne`].
+///
+/// It wraps another `ne` closure, or `!=` if none is given. NaN is treated as
+/// equal to NaN, so a NaN that persists is quiet, but the first NaN and the
+/// first value after a NaN are always reported, even if the wrapped closure
+/// would not report them.
+///
+/// ```rust
+/// # #[cfg(feature = "float")]
+/// # {
+/// use dbg_if::{was_ne, nan_ne_args, relative_ne_args};
+/// fn f(x: f32) -> bool {
+///     was_ne!(x, f32, nan_ne_args!(relative_ne_args!(max_relative = 0.5)))
+/// }
+/// assert!(f(1.0));
+/// assert!(!f(1.2));
+/// assert!(f(f32::NAN));
+/// assert!(!f(f32::NAN));
+/// assert!(f(1.2));
+/// # }
+/// ```
+///
+/// Without the float feature it still applies to values that are not equal to
+/// themselves, e.g., arrays containing NaN.
+#[macro_export]
+macro_rules! nan_ne_args {
+    () => {
+        |last_value, new_value| $crate::filter::nan_ne(last_value, new_value, |a, b| a != b)
+    };
+    ($ne:expr $(,)?) => {
+        |last_value, new_value| $crate::filter::nan_ne(last_value, new_value, $ne)
+    };
+}
+
+/// Expands to the first block, or to the second block when the "debug_only"
+/// feature is on and the calling crate is built without `debug_assertions`.
+/// Given statements, they only run in the first case. Disabled code is still
+/// type checked, so its imports are used, but it is folded away with its
+/// statics.
+#[doc(hidden)]
+#[cfg(not(feature = "debug_only"))]
+#[macro_export]
+macro_rules! enabled {
+    ({ $($on:tt)* } else { $($off:tt)* }) => {
+        { $($on)* }
+    };
+    ($($on:tt)*) => {
+        { $($on)* }
+    };
+}
+
+#[doc(hidden)]
+#[cfg(feature = "debug_only")]
+#[macro_export]
+macro_rules! enabled {
+    ({ $($on:tt)* } else { $($off:tt)* }) => {
+        if ::core::cfg!(debug_assertions) { $($on)* } else { $($off)* }
+    };
+    ($($on:tt)*) => {
+        if ::core::cfg!(debug_assertions) { $($on)* }
     };
 }
 
 #[doc(hidden)]
 #[macro_export]
-macro_rules! static_atomic {
-    ($name:ident: u8) => {
-        static $name: ::core::sync::atomic::AtomicU8 = ::core::sync::atomic::AtomicU8::new(0);
+macro_rules! filter_expr {
+    // Split on `|` into alternatives.
+    (@or $val:ident [$($alts:tt)*] [$($cur:tt)*] | $($rest:tt)*) => {
+        $crate::filter_expr!(@or $val [$($alts)* [$($cur)*]] [] $($rest)*)
+    };
+    (@or $val:ident [$($alts:tt)*] [$($cur:tt)*] || $($rest:tt)*) => {
+        $crate::filter_expr!(@or $val [$($alts)* [$($cur)*]] [] $($rest)*)
     };
-    ($name:ident: u16) => {
-        static $name: ::core::sync::atomic::AtomicU16 = ::core::sync::atomic::AtomicU16::new(0);
+    (@or $val:ident [$($alts:tt)*] [$($cur:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::filter_expr!(@or $val [$($alts)*] [$($cur)* $next] $($rest)*)
     };
-    ($name:ident: u32) => {
-        static $name: ::core::sync::atomic::AtomicU32 = ::core::sync::atomic::AtomicU32::new(0);
+    (@or $val:ident [$($alts:tt)*] [$($cur:tt)*]) => {
+        $crate::filter_expr!(@or_fold $val $($alts)* [$($cur)*])
     };
-    ($name:ident: u64) => {
-        static $name: ::core::sync::atomic::AtomicU64 = ::core::sync::atomic::AtomicU64::new(0);
+    (@or_fold $val:ident [$($alt:tt)*]) => {
+        $crate::filter_expr!(@and $val [] [] $($alt)*)
     };
-    ($name:ident: usize) => {
-        static $name: ::core::sync::atomic::AtomicUsize = ::core::sync::atomic::AtomicUsize::new(0);
+    (@or_fold $val:ident [$($alt:tt)*] $($more:tt)+) => {
+        $crate::filter::Or($crate::filter_expr!(@and $val [] [] $($alt)*),
+                           $crate::filter_expr!(@or_fold $val $($more)+))
     };
-    ($name:ident: i8) => {
-        static $name: ::core::sync::atomic::AtomicI8 = ::core::sync::atomic::AtomicI8::new(0);
+
+    // Split on `&` into conjuncts.
+    (@and $val:ident [$($all:tt)*] [$($cur:tt)*] & $($rest:tt)*) => {
+        $crate::filter_expr!(@and $val [$($all)* [$($cur)*]] [] $($rest)*)
+    };
+    (@and $val:ident [$($all:tt)*] [$($cur:tt)*] && $($rest:tt)*) => {
+        $crate::filter_expr!(@and $val [$($all)* [$($cur)*]] [] $($rest)*)
+    };
+    (@and $val:ident [$($all:tt)*] [$($cur:tt)*] $next:tt $($rest:tt)*) => {
+        $crate::filter_expr!(@and $val [$($all)*] [$($cur)* $next] $($rest)*)
+    };
+    (@and $val:ident [$($all:tt)*] [$($cur:tt)*]) => {
+        $crate::filter_expr!(@and_fold $val $($all)* [$($cur)*])
     };
-    ($name:ident: i16) => {
-        static $name: ::core::sync::atomic::AtomicI16 = ::core::sync::atomic::AtomicI16::new(0);
+    (@and_fold $val:ident [$($f:tt)*]) => {
+        $crate::filter_expr!(@leaf $val $($f)*)
     };
-    ($name:ident: i32) => {
-        static $name: ::core::sync::atomic::AtomicI32 = ::core::sync::atomic::AtomicI32::new(0);
+    (@and_fold $val:ident [$($f:tt)*] $($more:tt)+) => {
+        $crate::filter::And($crate::filter_expr!(@leaf $val $($f)*),
+                            $crate::filter_expr!(@and_fold $val $($more)+))
     };
-    ($name:ident: i64) => {
-        static $name: ::core::sync::atomic::AtomicI64 = ::core::sync::atomic::AtomicI64::new(0);
+
+    // Leaves are built-in filters, groups, or user-defined filter types.
+    (@leaf $val:ident Every) => {
+        $crate::filter::Every
     };
-    ($name:ident: isize) => {
-        static $name: ::core::sync::atomic::AtomicIsize = ::core::sync::atomic::AtomicIsize::new(0);
+    (@leaf $val:ident Once) => {{
+        static FILTER: $crate::filter::Once = $crate::filter::Once::new();
+        $crate::scope::local(&FILTER, $crate::filter::Once::new)
+    }};
+    (@leaf $val:ident EveryN($n:expr $(,)?)) => {{
+        static FILTER: $crate::filter::EveryN = $crate::filter::EveryN::new($n);
+        $crate::scope::local(&FILTER, || $crate::filter::EveryN::new($n))
+    }};
+    (@leaf $val:ident IfNe($type:tt $(,)?)) => {{
+        static FILTER: $crate::filter::IfNe<$crate::atomic_type!($type)> =
+            <$crate::filter::IfNe<$crate::atomic_type!($type)>>::new();
+        $crate::scope::local(&FILTER, <$crate::filter::IfNe<$crate::atomic_type!($type)>>::new)
+    }};
+    (@leaf $val:ident IfNe($type:tt, $ne:expr $(,)?)) => {{
+        static FILTER: $crate::filter::IfNe<$crate::atomic_type!($type)> =
+            <$crate::filter::IfNe<$crate::atomic_type!($type)>>::new();
+        $crate::filter::IfNeBy::new(
+            $crate::scope::local(&FILTER, <$crate::filter::IfNe<$crate::atomic_type!($type)>>::new),
+            $ne,
+        )
+    }};
+    (@leaf $val:ident IfHashNe) => {{
+        static FILTER: $crate::filter::IfHashNe = $crate::filter::IfHashNe::new();
+        $crate::scope::local(&FILTER, $crate::filter::IfHashNe::new)
+    }};
+    (@leaf $val:ident When($pred:expr $(,)?)) => {
+        $crate::filter::When::for_value(&$val, $pred)
     };
-    ($name:ident: f32) => {
-        $crate::static_atomic_float!($name: f32);
+    (@leaf $val:ident ($($group:tt)+)) => {
+        $crate::filter_expr!($val; $($group)+)
     };
-    ($name:ident: f64) => {
-        $crate::static_atomic_float!($name: f64);
+    (@leaf $val:ident $($filter:tt)+) => {{
+        static FILTER: $($filter)+ = <$($filter)+>::new();
+        $crate::scope::local(&FILTER, <$($filter)+>::new)
+    }};
+
+    ($val:ident; $($filter:tt)+) => {
+        $crate::filter_expr!(@or $val [] [] $($filter)+)
     };
 }
 
+#[doc(hidden)]
+#[macro_export]
+macro_rules! atomic_type {
+    (u8) => { ::core::sync::atomic::AtomicU8 };
+    (u16) => { ::core::sync::atomic::AtomicU16 };
+    (u32) => { ::core::sync::atomic::AtomicU32 };
+    (u64) => { $crate::filter::U64Cell };
+    (usize) => { ::core::sync::atomic::AtomicUsize };
+    (i8) => { ::core::sync::atomic::AtomicI8 };
+    (i16) => { ::core::sync::atomic::AtomicI16 };
+    (i32) => { ::core::sync::atomic::AtomicI32 };
+    (i64) => { $crate::filter::I64Cell };
+    (isize) => { ::core::sync::atomic::AtomicIsize };
+    (f32) => { $crate::atomic_float_type!(f32) };
+    (f64) => { $crate::atomic_float_type!(f64) };
+    ($type:tt) => { $crate::filter::Locked<$type> };
+}
+
 #[doc(hidden)]
 #[cfg(feature = "float")]
 #[macro_export]
-macro_rules! static_atomic_float {
-    ($name:ident: f32) => {
-        static $name: ::atomic_float::AtomicF32 = ::atomic_float::AtomicF32::new(0.0);
-    };
-    ($name:ident: f64) => {
-        static $name: ::atomic_float::AtomicF64 = ::atomic_float::AtomicF64::new(0.0);
-    };
+macro_rules! approx_value {
+    ($val:expr) => {{
+        use $crate::float::{ApproxElems as _, ApproxSelf as _};
+        (&$crate::float::Approx($val)).approx_value()
+    }};
+}
+
+#[doc(hidden)]
+#[cfg(feature = "float")]
+#[macro_export]
+macro_rules! atomic_float_type {
+    (f32) => { ::atomic_float::AtomicF32 };
+    (f64) => { ::atomic_float::AtomicF64 };
 }
 
 #[doc(hidden)]
 #[cfg(not(feature = "float"))]
 #[macro_export]
-macro_rules! static_atomic_float {
-    ($name:ident: $type:tt) => {
+macro_rules! atomic_float_type {
+    ($type:tt) => {
         compile_error!(
             "Feature \"float\" must be enabled on \"dbg_if\" crate to use atomic floats."
-        );
+        )
     };
 }
@@ -0,0 +1,155 @@
+//! What precedes the location of each line.
+//!
+//! When a change fires in a loop, it helps to know when. Each line may begin
+//! with the wall-clock time in UTC, the time elapsed since a printing macro was
+//! first reached or [`set_prefix`] was first called, whichever came first, and
+//! a tick counter that the program sets with [`set_tick`], e.g., once per
+//! frame.
+//!
+//! ```rust
+//! use dbg_if::{dbg_if_ne, prefix::{set_prefix, set_tick, Prefix}};
+//!
+//! set_prefix(Prefix { elapsed: true, ..Prefix::default() });
+//! for frame in 0..3u64 {
+//!     set_tick(frame);
+//!     dbg_if_ne!(frame / 2, u64);
+//! }
+//! // Outputs: [+0.000s #0 src/prefix.rs:9:5] frame / 2 = 0
+//! // Outputs: [+0.000s #2 src/prefix.rs:9:5] frame / 2 = 1
+//! ```
+//!
+//! The wall clock and elapsed time require the "std" feature.
+use core::{
+    fmt,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+/// Which prefixes to write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Prefix {
+    /// The wall-clock time in UTC, e.g., `12:34:56.789`.
+    pub wall_clock: bool,
+    /// The time elapsed since a printing macro was first reached or
+    /// [`set_prefix`] was first called, e.g., `+1.234s`.
+    pub elapsed: bool,
+    /// The tick given to [`set_tick`], e.g., `#42`.
+    pub tick: bool,
+}
+
+const WALL_CLOCK: u8 = 1 << 0;
+const ELAPSED: u8 = 1 << 1;
+const TICK: u8 = 1 << 2;
+
+static PREFIX: AtomicU8 = AtomicU8::new(0);
+#[cfg(target_has_atomic = "64")]
+static TICKS: core::sync::atomic::AtomicU64 = core::sync::atomic::AtomicU64::new(0);
+#[cfg(not(target_has_atomic = "64"))]
+static TICKS: crate::lock::Lock<u64> = crate::lock::Lock::new(0);
+
+/// Sets which prefixes to write.
+pub fn set_prefix(prefix: Prefix) {
+    #[cfg(feature = "std")]
+    start();
+    let mut flags = 0;
+    if prefix.wall_clock {
+        flags |= WALL_CLOCK;
+    }
+    if prefix.elapsed {
+        flags |= ELAPSED;
+    }
+    if prefix.tick {
+        flags |= TICK;
+    }
+    PREFIX.store(flags, Ordering::Relaxed);
+}
+
+/// Sets the tick, e.g., a frame or simulation step.
+///
+/// This also turns on the tick prefix.
+pub fn set_tick(tick: u64) {
+    #[cfg(target_has_atomic = "64")]
+    TICKS.store(tick, Ordering::Relaxed);
+    #[cfg(not(target_has_atomic = "64"))]
+    {
+        *TICKS.lock() = tick;
+    }
+    PREFIX.fetch_or(TICK, Ordering::Relaxed);
+}
+
+/// Returns the tick given to [`set_tick`].
+pub fn tick() -> u64 {
+    #[cfg(target_has_atomic = "64")]
+    return TICKS.load(Ordering::Relaxed);
+    #[cfg(not(target_has_atomic = "64"))]
+    return *TICKS.lock();
+}
+
+/// Returns when the elapsed clock started.
+#[cfg(feature = "std")]
+pub(crate) fn start() -> std::time::Instant {
+    static START: std::sync::OnceLock<std::time::Instant> = std::sync::OnceLock::new();
+    *START.get_or_init(std::time::Instant::now)
+}
+
+/// The prefixes that are on, as of when a record was made.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Stamp {
+    flags: u8,
+    #[cfg(feature = "std")]
+    now: std::time::Duration,
+    #[cfg(feature = "std")]
+    elapsed: std::time::Duration,
+    tick: u64,
+}
+
+impl Stamp {
+    /// Reads the clocks and tick for the prefixes that are on.
+    pub(crate) fn now() -> Self {
+        let flags = PREFIX.load(Ordering::Relaxed);
+        Stamp {
+            flags,
+            #[cfg(feature = "std")]
+            now: if flags & WALL_CLOCK != 0 {
+                std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap_or_default()
+            } else {
+                Default::default()
+            },
+            #[cfg(feature = "std")]
+            elapsed: if flags & ELAPSED != 0 {
+                start().elapsed()
+            } else {
+                Default::default()
+            },
+            tick: tick(),
+        }
+    }
+}
+
+/// Writes the prefixes that are on, each followed by a space.
+impl fmt::Display for Stamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        #[cfg(feature = "std")]
+        {
+            if self.flags & WALL_CLOCK != 0 {
+                let secs = self.now.as_secs() % (24 * 60 * 60);
+                write!(
+                    f,
+                    "{:02}:{:02}:{:02}.{:03} ",
+                    secs / 3600,
+                    secs / 60 % 60,
+                    secs % 60,
+                    self.now.subsec_millis()
+                )?;
+            }
+            if self.flags & ELAPSED != 0 {
+                write!(f, "+{:.3}s ", self.elapsed.as_secs_f64())?;
+            }
+        }
+        if self.flags & TICK != 0 {
+            write!(f, "#{} ", self.tick)?;
+        }
+        Ok(())
+    }
+}
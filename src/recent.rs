@@ -0,0 +1,122 @@
+//! The most recent records, kept in a ring buffer.
+//!
+//! As a flight recorder, the printing macros keep their records in memory
+//! instead of printing them, so a normal run stays quiet. The history is
+//! written when [`dump_recent`] is called or a thread panics.
+//!
+//! ```rust
+//! use dbg_if::{dbg_if_ne, dump_recent, recent};
+//! recent::flight_recorder(100);
+//! for i in 0..1000u32 {
+//!     dbg_if_ne!(i / 100, u32); // No output.
+//! }
+//! dump_recent(2);
+//! // Outputs: [src/recent.rs:5:5] i / 100 = 8
+//! //          [src/recent.rs:5:5] i / 100 = 9
+//! ```
+use crate::{
+    capture::DbgRecord,
+    lock::Lock,
+    prefix::Stamp,
+    write::{self, Header, Record},
+};
+use std::{
+    boxed::Box,
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Once,
+    },
+    vec::Vec,
+};
+
+static CAPACITY: AtomicUsize = AtomicUsize::new(0);
+static RECENT: Lock<VecDeque<(Stamp, DbgRecord)>> = Lock::new(VecDeque::new());
+
+/// Keeps the last `capacity` records whether they print or not; zero keeps
+/// none.
+pub fn set_capacity(capacity: usize) {
+    CAPACITY.store(capacity, Ordering::Relaxed);
+    let mut recent = RECENT.lock();
+    let excess = recent.len().saturating_sub(capacity);
+    recent.drain(..excess);
+}
+
+/// Returns how many records are kept.
+pub fn capacity() -> usize {
+    CAPACITY.load(Ordering::Relaxed)
+}
+
+/// Keeps the last `capacity` records instead of printing them, and sets a
+/// panic hook that writes them after the prior hook. Calling it again only
+/// changes how many records are kept.
+pub fn flight_recorder(capacity: usize) {
+    static HOOK: Once = Once::new();
+    set_capacity(capacity);
+    write::set_sink(discard);
+    HOOK.call_once(|| {
+        let hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            hook(info);
+            // The hook that `install_panic_hook` sets writes them too.
+            if !crate::dump::is_installed() {
+                try_dump_recent(CAPACITY.load(Ordering::Relaxed));
+            }
+        }));
+    });
+}
+
+/// Writes the last `n` records kept, oldest first, to the
+/// [writer](crate::write::set_writer) as they would have been printed.
+pub fn dump_recent(n: usize) {
+    write_recent(&RECENT.lock(), n);
+}
+
+/// Returns the last `n` records kept, oldest first.
+pub fn recent(n: usize) -> Vec<DbgRecord> {
+    let recent = RECENT.lock();
+    let skip = recent.len().saturating_sub(n);
+    recent.iter().skip(skip).map(|(_, record)| record.clone()).collect()
+}
+
+/// Drops the record. This is the [sink](crate::write::set_sink) that
+/// [`flight_recorder`] sets.
+pub fn discard(_record: &Record<'_>) {}
+
+/// Keeps the record if records are being kept.
+pub(crate) fn keep(record: &Record<'_>) {
+    let capacity = CAPACITY.load(Ordering::Relaxed);
+    if capacity == 0 {
+        return;
+    }
+    let record = (Stamp::now(), DbgRecord::from(record));
+    let mut recent = RECENT.lock();
+    while recent.len() >= capacity {
+        recent.pop_front();
+    }
+    recent.push_back(record);
+}
+
+/// Writes the last `n` records like [`dump_recent`]. Returns false if another
+/// thread or a panic holds them.
+pub(crate) fn try_dump_recent(n: usize) -> bool {
+    match RECENT.try_lock() {
+        Some(recent) => {
+            write_recent(&recent, n);
+            true
+        }
+        None => false,
+    }
+}
+
+fn write_recent(recent: &VecDeque<(Stamp, DbgRecord)>, n: usize) {
+    let writer = write::writer();
+    for (stamp, record) in recent.iter().skip(recent.len().saturating_sub(n)) {
+        let header = Header(*stamp, record.location);
+        if record.expr.is_empty() {
+            writer(format_args!("{}", header));
+        } else {
+            writer(format_args!("{} {}", header, record));
+        }
+    }
+}
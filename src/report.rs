@@ -0,0 +1,332 @@
+//! A table of the call sites of the printing macros.
+//!
+//! Each call site of [`dbg_once!`](crate::dbg_once),
+//! [`dbg_if_ne!`](crate::dbg_if_ne), and the other printing macros counts how
+//! often it was reached and how often it printed. It is listed from the first
+//! time it is reached. [`report`] writes a table of them.
+//!
+//! ```rust
+//! use dbg_if::{dbg_if_ne, report};
+//! report::keep_last();
+//! for i in 0..10u8 {
+//!     dbg_if_ne!(i / 5, u8);
+//! }
+//! report();
+//! // Outputs: location           expr   hits  prints  last  changed
+//! //          src/report.rs:4:5  i / 5    10       2  1     +0.000s
+//! ```
+//!
+//! The last value and when it changed are kept once [`keep_last`] or
+//! [`report_on_drop`] is called, and require the "std" feature.
+use crate::{format::Value, write::Location};
+use core::{
+    fmt, ptr,
+    sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering},
+};
+#[cfg(feature = "std")]
+use {crate::lock::Lock, std::string::String, std::time::Duration, std::vec::Vec};
+
+static SITES: AtomicPtr<Site> = AtomicPtr::new(ptr::null_mut());
+#[cfg(feature = "std")]
+static OBSERVE: AtomicBool = AtomicBool::new(false);
+#[cfg(feature = "std")]
+static KEEP_LAST: AtomicBool = AtomicBool::new(false);
+
+/// A call site of a printing macro.
+#[derive(Debug)]
+pub struct Site {
+    location: Location,
+    expr: &'static str,
+    hits: AtomicUsize,
+    prints: AtomicUsize,
+    enabled: AtomicBool,
+    listed: AtomicBool,
+    next: AtomicPtr<Site>,
+    #[cfg(feature = "std")]
+    last: Lock<Option<(String, Duration)>>,
+    #[cfg(feature = "std")]
+    observed: Lock<Option<String>>,
+}
+
+impl Site {
+    #[doc(hidden)]
+    pub const fn new(location: Location, expr: &'static str) -> Self {
+        Site {
+            location,
+            expr,
+            hits: AtomicUsize::new(0),
+            prints: AtomicUsize::new(0),
+            enabled: AtomicBool::new(true),
+            listed: AtomicBool::new(false),
+            next: AtomicPtr::new(ptr::null_mut()),
+            #[cfg(feature = "std")]
+            last: Lock::new(None),
+            #[cfg(feature = "std")]
+            observed: Lock::new(None),
+        }
+    }
+
+    /// Counts a hit, and the first time lists the call site and starts the
+    /// elapsed clock if it has not started.
+    #[doc(hidden)]
+    pub fn hit(&'static self) {
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        if self.listed.load(Ordering::Relaxed) || self.listed.swap(true, Ordering::Relaxed) {
+            return;
+        }
+        #[cfg(feature = "std")]
+        crate::prefix::start();
+        let mut head = SITES.load(Ordering::Relaxed);
+        loop {
+            self.next.store(head, Ordering::Relaxed);
+            match SITES.compare_exchange_weak(
+                head,
+                self as *const Site as *mut Site,
+                Ordering::Release,
+                Ordering::Relaxed,
+            ) {
+                Ok(_) => break,
+                Err(next) => head = next,
+            }
+        }
+    }
+
+    /// Keeps the value reached with if values are being observed.
+    #[doc(hidden)]
+    #[cfg_attr(not(feature = "std"), allow(unused_variables))]
+    pub fn observe(&self, value: Value<'_>) {
+        #[cfg(feature = "std")]
+        if OBSERVE.load(Ordering::Relaxed) && self.is_enabled() {
+            use core::fmt::Write as _;
+            let mut observed = self.observed.lock();
+            let observed = observed.get_or_insert_with(String::new);
+            observed.clear();
+            let options = crate::format::Options::new().one_line();
+            let _ = write!(observed, "{}", options.apply(value));
+        }
+    }
+
+    /// Counts a print and keeps the value printed if last values are kept.
+    #[cfg_attr(not(feature = "std"), allow(unused_variables))]
+    pub(crate) fn print(&self, value: impl fmt::Display) {
+        self.prints.fetch_add(1, Ordering::Relaxed);
+        #[cfg(feature = "std")]
+        if KEEP_LAST.load(Ordering::Relaxed) {
+            use core::fmt::Write as _;
+            let changed = crate::prefix::start().elapsed();
+            let mut last = self.last.lock();
+            let (last, when) = last.get_or_insert_with(Default::default);
+            last.clear();
+            let _ = write!(last, "{}", value);
+            *when = changed;
+        }
+    }
+
+    /// Returns where the macro was called.
+    pub fn location(&self) -> Location {
+        self.location
+    }
+
+    /// Returns the expression, which is empty for `dbg_once!()`.
+    pub fn expr(&self) -> &'static str {
+        self.expr
+    }
+
+    /// Returns false if the call site was turned off.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Turns the call site on or off. While it is off, it neither prints nor
+    /// keeps its value, but still counts hits.
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Returns how many times the call site was reached.
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// Returns how many times the call site printed.
+    pub fn prints(&self) -> usize {
+        self.prints.load(Ordering::Relaxed)
+    }
+
+    /// Returns the value last printed on one line since [`keep_last`], if any.
+    #[cfg(feature = "std")]
+    pub fn last_value(&self) -> Option<String> {
+        self.last.lock().as_ref().map(|(value, _)| value.clone())
+    }
+
+    /// Returns the value last reached with on one line, whether it printed or
+    /// not, if values are being observed, e.g., after
+    /// [`install_panic_hook`](crate::dump::install_panic_hook).
+    #[cfg(feature = "std")]
+    pub fn last_observed(&self) -> Option<String> {
+        self.observed.lock().clone()
+    }
+
+    /// Returns the last value observed or an empty string, or `None` if
+    /// another thread or a panic holds it.
+    #[cfg(feature = "std")]
+    pub(crate) fn try_last_observed(&self) -> Option<String> {
+        Some(self.observed.try_lock()?.clone().unwrap_or_default())
+    }
+
+    /// Returns when the call site last printed since [`keep_last`] as the time
+    /// since the elapsed clock started; see
+    /// [`Prefix::elapsed`](crate::prefix::Prefix::elapsed).
+    #[cfg(feature = "std")]
+    pub fn last_changed(&self) -> Option<Duration> {
+        self.last.lock().as_ref().map(|(_, changed)| *changed)
+    }
+}
+
+/// Returns the call sites that have been reached, most recent first.
+pub fn sites() -> Sites {
+    Sites(SITES.load(Ordering::Acquire))
+}
+
+/// An iterator over the call sites that have been reached.
+#[derive(Debug, Clone)]
+pub struct Sites(*const Site);
+
+impl Iterator for Sites {
+    type Item = &'static Site;
+
+    fn next(&mut self) -> Option<&'static Site> {
+        // SAFETY: Only `Site::hit` adds to the list and it adds statics.
+        let site = unsafe { self.0.as_ref()? };
+        self.0 = site.next.load(Ordering::Acquire);
+        Some(site)
+    }
+}
+
+/// Writes a table of the call sites that have been reached to the
+/// [writer](crate::write::set_writer), one line per call site.
+pub fn report() {
+    #[cfg(feature = "std")]
+    crate::background::flush();
+    let writer = crate::write::writer();
+    #[cfg(feature = "std")]
+    {
+        let rows: Vec<[String; 6]> = sorted()
+            .into_iter()
+            .map(|site| {
+                let last = site.last.lock();
+                [
+                    site.location.to_string(),
+                    site.expr.into(),
+                    site.hits().to_string(),
+                    site.prints().to_string(),
+                    last.as_ref().map_or_else(String::new, |(value, _)| value.clone()),
+                    last.as_ref().map_or_else(String::new, |(_, changed)| {
+                        std::format!("+{:.3}s", changed.as_secs_f64())
+                    }),
+                ]
+            })
+            .collect();
+        write_table(
+            writer,
+            ["location", "expr", "hits", "prints", "last", "changed"],
+            &rows,
+        );
+    }
+    #[cfg(not(feature = "std"))]
+    {
+        writer(format_args!("location  expr  hits  prints"));
+        for site in sites() {
+            writer(format_args!(
+                "{}  {}  {}  {}",
+                site.location,
+                site.expr,
+                site.hits(),
+                site.prints()
+            ));
+        }
+    }
+}
+
+/// Keeps the value each call site prints from now on, and when, for the last
+/// and changed columns of [`report`]. Without it, printing does not format the
+/// value a second time to keep it. Does nothing without the "std" feature.
+pub fn keep_last() {
+    #[cfg(feature = "std")]
+    KEEP_LAST.store(true, Ordering::Relaxed);
+}
+
+/// Starts or stops keeping the value each call site is reached with.
+#[cfg(feature = "std")]
+pub(crate) fn set_observe(observe: bool) {
+    OBSERVE.store(observe, Ordering::Relaxed);
+}
+
+/// Returns true if each call site keeps the value it is reached with.
+#[cfg(feature = "std")]
+pub(crate) fn is_observing() -> bool {
+    OBSERVE.load(Ordering::Relaxed)
+}
+
+/// Returns the call sites that have been reached in order of location.
+#[cfg(feature = "std")]
+pub(crate) fn sorted() -> Vec<&'static Site> {
+    let mut sites: Vec<_> = sites().collect();
+    sites.sort_by_key(|site| (site.location.file, site.location.line, site.location.column));
+    sites
+}
+
+/// Writes the header and rows as columns. The counts in the third and fourth
+/// columns are aligned right.
+#[cfg(feature = "std")]
+pub(crate) fn write_table<const N: usize>(
+    mut writer: impl FnMut(fmt::Arguments<'_>),
+    header: [&str; N],
+    rows: &[[String; N]],
+) {
+    let header = header.map(String::from);
+    let mut widths = [0; N];
+    for row in core::iter::once(&header).chain(rows) {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let mut line = String::new();
+    for row in core::iter::once(&header).chain(rows) {
+        line.clear();
+        for (i, (cell, width)) in row.iter().zip(widths).enumerate() {
+            use core::fmt::Write as _;
+            let _ = match i {
+                _ if i + 1 == N => write!(line, "{}", cell),
+                2 | 3 => write!(line, "{:>width$}  ", cell),
+                _ => write!(line, "{:<width$}  ", cell),
+            };
+        }
+        writer(format_args!("{}", line));
+    }
+}
+
+/// Calls [`report`] when dropped, e.g., at the end of `main`, and calls
+/// [`keep_last`] now.
+///
+/// ```rust
+/// // At the start of `main`:
+/// let _report = dbg_if::report::report_on_drop();
+/// ```
+pub fn report_on_drop() -> ReportGuard {
+    keep_last();
+    ReportGuard { _private: () }
+}
+
+/// Calls [`report`] when dropped.
+#[must_use = "the report is written when the guard is dropped"]
+#[derive(Debug)]
+pub struct ReportGuard {
+    _private: (),
+}
+
+impl Drop for ReportGuard {
+    fn drop(&mut self) {
+        report();
+    }
+}
@@ -0,0 +1,173 @@
+// Scopes require "std"; without it, link to docs.rs.
+#![cfg_attr(
+    not(feature = "std"),
+    doc = "[`DbgScope`]: https://docs.rs/dbg_if/latest/dbg_if/struct.DbgScope.html"
+)]
+//! Fresh call-site state for a thread.
+//!
+//! The state behind [`once!`](crate::once), [`was_ne!`](crate::was_ne), and
+//! the other macros is kept in a static per call site, so it is shared by the
+//! whole process. Two tests that call the same function therefore interfere.
+//! While a [`DbgScope`] is alive, the macros on its thread use state of their
+//! own that starts fresh and is dropped with the scope.
+//!
+//! ```rust
+//! use dbg_if::{once, DbgScope};
+//! fn f() -> bool {
+//!     once!()
+//! }
+//! assert!(f());
+//! {
+//!     let _scope = DbgScope::new();
+//!     assert!(f()); // Fresh state.
+//!     assert!(!f());
+//! }
+//! assert!(!f()); // Back to the process-wide state.
+//! ```
+//!
+//! Scopes require the "std" feature.
+use crate::DbgFilter;
+use core::ops::Deref;
+#[cfg(feature = "std")]
+use std::{
+    any::Any,
+    cell::RefCell,
+    collections::HashMap,
+    marker::PhantomData,
+    rc::Rc,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+#[cfg(feature = "std")]
+static ACTIVE: AtomicUsize = AtomicUsize::new(0);
+
+#[cfg(feature = "std")]
+std::thread_local! {
+    static SCOPES: RefCell<Vec<HashMap<usize, Rc<dyn Any>>>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Gives this thread fresh call-site state until it is dropped.
+///
+/// Scopes nest; the most recent one is used. Dropping a scope also ends the
+/// scopes started after it.
+///
+/// ```rust
+/// use dbg_if::{was_ne, DbgScope};
+/// fn f(x: u8) -> bool {
+///     was_ne!(x, u8)
+/// }
+///
+/// // Each test starts with a scope.
+/// fn test_a() {
+///     let _scope = DbgScope::new();
+///     assert!(f(1));
+/// }
+///
+/// fn test_b() {
+///     let _scope = DbgScope::new();
+///     assert!(f(1)); // Passes even though `test_a` ran first.
+/// }
+/// test_a();
+/// test_b();
+/// ```
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct DbgScope {
+    depth: usize,
+    // Must be dropped on the thread it was made on.
+    _not_send: PhantomData<*const ()>,
+}
+
+#[cfg(feature = "std")]
+impl DbgScope {
+    /// Starts a scope on this thread.
+    pub fn new() -> Self {
+        let depth = SCOPES.with_borrow_mut(|scopes| {
+            scopes.push(HashMap::new());
+            scopes.len()
+        });
+        ACTIVE.fetch_add(1, Ordering::Relaxed);
+        DbgScope {
+            depth,
+            _not_send: PhantomData,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for DbgScope {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl Drop for DbgScope {
+    fn drop(&mut self) {
+        ACTIVE.fetch_sub(1, Ordering::Relaxed);
+        // Drop the state after the borrow in case dropping it uses a macro.
+        let state = SCOPES.try_with(|scopes| {
+            let mut scopes = scopes.borrow_mut();
+            let depth = scopes.len().min(self.depth - 1);
+            scopes.split_off(depth)
+        });
+        drop(state);
+    }
+}
+
+/// The state of a call site: its static or the current scope's copy.
+#[doc(hidden)]
+pub enum Local<T: 'static> {
+    Global(&'static T),
+    #[cfg(feature = "std")]
+    Scoped(Rc<T>),
+}
+
+impl<T> Deref for Local<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        match self {
+            Local::Global(global) => global,
+            #[cfg(feature = "std")]
+            Local::Scoped(scoped) => scoped,
+        }
+    }
+}
+
+impl<T: DbgFilter<V>, V: ?Sized> DbgFilter<V> for Local<T> {
+    fn should_print(&self, value: &V) -> bool {
+        (**self).should_print(value)
+    }
+}
+
+/// Returns the current scope's state for the call site whose static is
+/// `global`, made with `new` on first use, or `global` if there is no scope.
+#[doc(hidden)]
+#[cfg(feature = "std")]
+pub fn local<T: 'static, F: FnOnce() -> T>(global: &'static T, new: F) -> Local<T> {
+    if ACTIVE.load(Ordering::Relaxed) == 0 {
+        return Local::Global(global);
+    }
+    SCOPES
+        .try_with(|scopes| {
+            let mut scopes = scopes.borrow_mut();
+            let state = scopes
+                .last_mut()?
+                .entry(global as *const T as usize)
+                .or_insert_with(|| Rc::new(new()))
+                .clone();
+            // Zero-sized statics may share an address, but have no state.
+            state.downcast::<T>().ok()
+        })
+        .ok()
+        .flatten()
+        .map_or(Local::Global(global), Local::Scoped)
+}
+
+/// Returns `global`.
+#[doc(hidden)]
+#[cfg(not(feature = "std"))]
+pub fn local<T: 'static, F: FnOnce() -> T>(global: &'static T, _new: F) -> Local<T> {
+    Local::Global(global)
+}
@@ -0,0 +1,36 @@
+//! Writes a report when the process receives `SIGUSR1`.
+//!
+//! For a long-running process, `kill -USR1 <pid>` writes the
+//! [report](crate::report()) of every call site to the
+//! [writer](crate::write::set_writer), followed by the records kept by the
+//! [flight recorder](crate::recent), if any. The signal handler only wakes a
+//! background thread, which writes the report outside of the signal handler.
+//!
+//! ```rust,no_run
+//! dbg_if::signal::report_on_sigusr1().unwrap();
+//! ```
+//!
+//! This requires the "signal" feature and a Unix target.
+use crate::recent;
+use signal_hook::{consts::SIGUSR1, iterator::Signals};
+use std::io;
+
+/// Starts a thread that writes a report each time the process receives
+/// `SIGUSR1`, and calls [`keep_last`](crate::report::keep_last).
+pub fn report_on_sigusr1() -> io::Result<()> {
+    let mut signals = Signals::new([SIGUSR1])?;
+    crate::report::keep_last();
+    std::thread::Builder::new()
+        .name("dbg_if-signal".into())
+        .spawn(move || {
+            for _ in signals.forever() {
+                crate::report();
+                let capacity = recent::capacity();
+                if capacity > 0 {
+                    crate::write::writer()(format_args!("dbg_if: recent records"));
+                    recent::dump_recent(capacity);
+                }
+            }
+        })?;
+    Ok(())
+}
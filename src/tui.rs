@@ -0,0 +1,240 @@
+//! A terminal dashboard of the call sites of the printing macros.
+//!
+//! [`run`] takes over the terminal and shows a row per call site with its
+//! expression, location, latest value, and how many times it changed, and a
+//! sparkline of the selected call site if its values are numbers. The macros
+//! do not print while it runs.
+//!
+//! - `↑`/`k` and `↓`/`j` select a call site.
+//! - `Space` or `Enter` turns the selected call site on or off.
+//! - `q` or `Esc` quits.
+//!
+//! Once it quits, the macros print again as before, but call sites turned off
+//! stay off until [`Site::set_enabled`] turns them on.
+//!
+//! ```rust,no_run
+//! use dbg_if::dbg_if_ne;
+//! use std::time::Duration;
+//!
+//! std::thread::spawn(|| dbg_if::tui::run(Duration::from_millis(100)));
+//! for i in 0..u32::MAX {
+//!     dbg_if_ne!(i % 100, u32);
+//! }
+//! ```
+//!
+//! A [`Dashboard`] can also be drawn to any [`ratatui`] backend, e.g., to test
+//! it with [`TestBackend`](ratatui::backend::TestBackend).
+//!
+//! This requires the "tui" feature.
+use crate::{
+    recent,
+    report::{self, Site},
+    write,
+};
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout},
+    style::{Modifier, Style},
+    widgets::{Block, Row, Sparkline, Table, TableState},
+    Frame,
+};
+use std::{
+    collections::{HashMap, VecDeque},
+    io,
+    string::{String, ToString},
+    time::{Duration, Instant},
+    vec::Vec,
+};
+
+/// The version of `ratatui` the dashboard is drawn with.
+pub use ratatui;
+
+/// How many values a sparkline shows.
+const HISTORY: usize = 120;
+
+/// Takes over the terminal and shows a [`Dashboard`], refreshed every
+/// `interval`, until `q` or `Esc` is pressed. Then, or if the terminal cannot
+/// be set up or drawn, it restores the terminal and the sink.
+pub fn run(interval: Duration) -> io::Result<()> {
+    let mut dashboard = Dashboard::new();
+    let sink = write::sink();
+    write::set_sink(recent::discard);
+    let result = (|| {
+        // Restored below also if it fails partway, e.g., after raw mode.
+        let mut terminal = ratatui::try_init()?;
+        loop {
+            dashboard.sample();
+            terminal.draw(|frame| dashboard.render(frame))?;
+            let deadline = Instant::now() + interval;
+            while let Some(timeout) = deadline.checked_duration_since(Instant::now()) {
+                if !event::poll(timeout)? {
+                    break;
+                }
+                if let Event::Key(key) = event::read()? {
+                    if key.kind == KeyEventKind::Press && !dashboard.key(key.code) {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    })();
+    ratatui::restore();
+    write::set_sink(sink);
+    result
+}
+
+/// The state of the dashboard: the selected call site and the recent values
+/// of each.
+#[derive(Debug)]
+pub struct Dashboard {
+    table: TableState,
+    history: HashMap<usize, VecDeque<f64>>,
+    observing: bool,
+}
+
+impl Dashboard {
+    /// Makes a dashboard and starts keeping the latest value of each call
+    /// site until it is dropped.
+    pub fn new() -> Self {
+        let observing = report::is_observing();
+        report::set_observe(true);
+        Dashboard {
+            table: TableState::default().with_selected(0),
+            history: HashMap::new(),
+            observing,
+        }
+    }
+
+    /// Records the latest value of each call site that is a number for its
+    /// sparkline. Call this once per refresh.
+    pub fn sample(&mut self) {
+        for site in report::sites() {
+            let Some(value) = site.last_observed().and_then(|v| v.parse::<f64>().ok()) else {
+                continue;
+            };
+            let history = self
+                .history
+                .entry(site as *const Site as usize)
+                .or_default();
+            if history.len() == HISTORY {
+                history.pop_front();
+            }
+            history.push_back(value);
+        }
+    }
+
+    /// Handles a key press. Returns false if the dashboard should quit.
+    pub fn key(&mut self, key: KeyCode) -> bool {
+        match key {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Up | KeyCode::Char('k') => self.table.select_previous(),
+            KeyCode::Down | KeyCode::Char('j') => {
+                let last = report::sites().count().saturating_sub(1);
+                self.table.select_next();
+                self.table.select(self.table.selected().map(|i| i.min(last)));
+            }
+            KeyCode::Char(' ') | KeyCode::Enter => {
+                if let Some(site) = self.selected() {
+                    site.set_enabled(!site.is_enabled());
+                }
+            }
+            _ => {}
+        }
+        true
+    }
+
+    /// Returns the selected call site, if any.
+    pub fn selected(&self) -> Option<&'static Site> {
+        report::sorted().get(self.table.selected()?).copied()
+    }
+
+    /// Draws the dashboard over the whole frame.
+    pub fn render(&mut self, frame: &mut Frame<'_>) {
+        let sites = report::sorted();
+        if let Some(selected) = self.table.selected() {
+            self.table
+                .select(Some(selected.min(sites.len().saturating_sub(1))));
+        }
+        let location_width = sites
+            .iter()
+            .map(|site| site.location().to_string().len())
+            .max()
+            .unwrap_or(0)
+            .max("location".len());
+        let rows = sites.iter().map(|site| {
+            let row = Row::new([
+                if site.is_enabled() { "on" } else { "off" }.to_string(),
+                site.expr().to_string(),
+                site.location().to_string(),
+                site.last_observed().unwrap_or_default(),
+                site.prints().to_string(),
+            ]);
+            if site.is_enabled() {
+                row
+            } else {
+                row.style(Style::new().add_modifier(Modifier::DIM))
+            }
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(3),
+                Constraint::Fill(1),
+                Constraint::Length(location_width as u16),
+                Constraint::Fill(2),
+                Constraint::Length(7),
+            ],
+        )
+        .header(
+            Row::new(["", "expr", "location", "value", "changes"])
+                .style(Style::new().add_modifier(Modifier::BOLD)),
+        )
+        .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+        .block(Block::bordered().title(" dbg_if "));
+        let [table_area, sparkline_area] =
+            Layout::vertical([Constraint::Min(3), Constraint::Length(5)]).areas(frame.area());
+        frame.render_stateful_widget(table, table_area, &mut self.table);
+
+        let selected = self.selected();
+        let title = selected.map_or_else(String::new, |site| std::format!(" {} ", site.expr()));
+        let data = selected
+            .and_then(|site| self.history.get(&(site as *const Site as usize)))
+            .map_or_else(Vec::new, scale);
+        frame.render_widget(
+            Sparkline::default()
+                .block(Block::bordered().title(title))
+                .data(&data),
+            sparkline_area,
+        );
+    }
+}
+
+impl Default for Dashboard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for Dashboard {
+    fn drop(&mut self) {
+        report::set_observe(self.observing);
+    }
+}
+
+/// Scales the values from 1 for the least to 101 for the greatest so that
+/// every value has a bar.
+fn scale(history: &VecDeque<f64>) -> Vec<u64> {
+    let min = history.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = history.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let range = max - min;
+    history
+        .iter()
+        .map(|value| {
+            if range > 0.0 {
+                ((value - min) / range * 100.0).round() as u64 + 1
+            } else {
+                1
+            }
+        })
+        .collect()
+}
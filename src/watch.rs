@@ -0,0 +1,133 @@
+//! Shows the latest value of every call site in a table that is redrawn
+//! periodically, like the `watch` command.
+//!
+//! For values that change too often to print each change, [`start`] stops the
+//! printing macros from printing. Each call site keeps the value it was last
+//! reached with instead, and a background thread writes a table with one row
+//! per call site every interval. On a terminal the table is redrawn in place;
+//! otherwise, e.g., in a [file](crate::file), each table follows the last.
+//! Once the guard is dropped, the macros print again.
+//!
+//! ```rust
+//! use dbg_if::{dbg_if_ne, watch};
+//! use std::time::Duration;
+//!
+//! let _watch = watch::start(Duration::from_millis(250));
+//! for i in 0..1000u32 {
+//!     dbg_if_ne!(i / 10, u32); // No output.
+//! }
+//! // Outputs: dbg_if: watch +0.250s
+//! //          location          expr    hits  changes  value
+//! //          src/watch.rs:6:5  i / 10  1000      100  99
+//! ```
+use crate::{
+    recent,
+    report::{self, write_table},
+    write::{self, Record},
+};
+use std::{
+    io::IsTerminal,
+    string::{String, ToString},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::JoinHandle,
+    time::Duration,
+    vec::Vec,
+};
+
+/// Stops the table when dropped after writing it a last time, and restores
+/// the sink and whether values were kept.
+#[must_use = "the table stops when the guard is dropped"]
+#[derive(Debug)]
+pub struct WatchGuard {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+    sink: fn(&Record<'_>),
+    observing: bool,
+}
+
+impl Drop for WatchGuard {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            thread.thread().unpark();
+            let _ = thread.join();
+        }
+        write::set_sink(self.sink);
+        report::set_observe(self.observing);
+    }
+}
+
+/// Stops printing and writes the table of call sites every `interval` until
+/// the returned guard is dropped.
+pub fn start(interval: Duration) -> WatchGuard {
+    let sink = write::sink();
+    let observing = report::is_observing();
+    report::set_observe(true);
+    write::set_sink(recent::discard);
+    let stop = Arc::new(AtomicBool::new(false));
+    let thread = {
+        let stop = stop.clone();
+        std::thread::Builder::new()
+            .name("dbg_if-watch".into())
+            .spawn(move || {
+                let in_place = write::to_stderr() && std::io::stderr().is_terminal();
+                let mut drawn = 0;
+                loop {
+                    std::thread::park_timeout(interval);
+                    let stopping = stop.load(Ordering::Relaxed);
+                    drawn = draw(in_place.then_some(drawn));
+                    if stopping {
+                        break;
+                    }
+                }
+            })
+            .expect("spawn dbg_if-watch thread")
+    };
+    WatchGuard {
+        stop,
+        thread: Some(thread),
+        sink,
+        observing,
+    }
+}
+
+/// Writes the table, first erasing the `drawn` lines written last time if
+/// drawing in place, and returns how many lines it wrote.
+fn draw(drawn: Option<usize>) -> usize {
+    let rows: Vec<[String; 5]> = report::sorted()
+        .into_iter()
+        .map(|site| {
+            [
+                site.location().to_string(),
+                site.expr().into(),
+                site.hits().to_string(),
+                site.prints().to_string(),
+                site.last_observed().unwrap_or_default(),
+            ]
+        })
+        .collect();
+    let mut lines = Vec::with_capacity(rows.len() + 2);
+    lines.push(std::format!(
+        "dbg_if: watch +{:.3}s",
+        crate::prefix::start().elapsed().as_secs_f64()
+    ));
+    write_table(
+        |line| lines.push(line.to_string()),
+        ["location", "expr", "hits", "changes", "value"],
+        &rows,
+    );
+    let writer = write::writer();
+    for (i, line) in lines.iter().enumerate() {
+        match drawn {
+            // Move up to the first line drawn and erase to the end.
+            Some(drawn) if i == 0 && drawn > 0 => {
+                writer(format_args!("\x1b[{}A\x1b[J{}", drawn, line))
+            }
+            _ => writer(format_args!("{}", line)),
+        }
+    }
+    lines.len()
+}
@@ -0,0 +1,459 @@
+//! Where the printing macros write to.
+//!
+//! With the "std" feature, which is on by default, each line is written to
+//! stderr like [`std::dbg!`], or to the file named by the `DBG_IF_FILE`
+//! environment variable. Without it, lines are discarded until a writer is
+//! set with [`set_writer`].
+//!
+//! Before it becomes a line, each [`Record`] goes to a sink, which can be
+//! replaced with [`set_sink`] to send records elsewhere, e.g., to `tracing` or
+//! `log`.
+//!
+//! ```rust
+//! use core::fmt;
+//! use dbg_if::{dbg_once, write::set_writer};
+//!
+//! fn to_stdout(line: fmt::Arguments<'_>) {
+//!     println!("{}", line);
+//! }
+//!
+//! set_writer(to_stdout);
+//! dbg_once!(1); // Outputs to stdout: [src/write.rs:9:1] 1 = 1
+//! ```
+use crate::{
+    format::{Options, Value},
+    prefix::Stamp,
+    report::Site,
+};
+use core::{
+    fmt,
+    sync::atomic::{AtomicPtr, Ordering},
+};
+
+/// The source location of a call site.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Location {
+    /// The file as given by [`file!`].
+    pub file: &'static str,
+    /// The line as given by [`line!`].
+    pub line: u32,
+    /// The column as given by [`column!`].
+    pub column: u32,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.line, self.column)
+    }
+}
+
+static WRITER: AtomicPtr<()> = AtomicPtr::new(core::ptr::null_mut());
+static SINK: AtomicPtr<()> = AtomicPtr::new(core::ptr::null_mut());
+
+/// What a printing macro reports.
+#[derive(Clone, Copy)]
+pub enum Record<'a> {
+    /// An expression and its value, written as `[location] expr = value`.
+    Value {
+        /// Where the macro was called.
+        location: Location,
+        /// The expression.
+        expr: &'a str,
+        /// The value as formatted by [`format`](mod@crate::format).
+        value: &'a dyn fmt::Display,
+    },
+    /// An element that changed, written as `[location] expr[index]: old ->
+    /// new`.
+    Change {
+        /// Where the macro was called.
+        location: Location,
+        /// The expression.
+        expr: &'a str,
+        /// The index of the element.
+        index: usize,
+        /// The element before, if any.
+        old: Option<&'a dyn fmt::Debug>,
+        /// The element now, if any.
+        new: Option<&'a dyn fmt::Debug>,
+    },
+    /// A call site without an expression, written as `[location]`.
+    Location {
+        /// Where the macro was called.
+        location: Location,
+    },
+}
+
+impl Record<'_> {
+    /// Returns where the macro was called.
+    pub fn location(&self) -> Location {
+        match *self {
+            Record::Value { location, .. }
+            | Record::Change { location, .. }
+            | Record::Location { location } => location,
+        }
+    }
+}
+
+/// Writes the record without its location, e.g., `expr = value`.
+impl fmt::Display for Record<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Record::Value { expr, value, .. } => write!(f, "{} = {}", expr, value),
+            Record::Change {
+                expr,
+                index,
+                old,
+                new,
+                ..
+            } => write!(f, "{}[{}]: {} -> {}", expr, index, Elem(old), Elem(new)),
+            Record::Location { .. } => Ok(()),
+        }
+    }
+}
+
+impl fmt::Debug for Record<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Record")
+            .field("location", &self.location())
+            .field("line", &format_args!("{}", self))
+            .finish()
+    }
+}
+
+/// Sets the function each line of output is written to.
+///
+/// The line does not include a trailing newline. This is how output reaches
+/// a serial port, `defmt`, or a [`core::fmt::Write`] in `no_std` builds.
+pub fn set_writer(writer: fn(fmt::Arguments<'_>)) {
+    WRITER.store(writer as *mut (), Ordering::Release);
+}
+
+/// Sets the function each record is sent to instead of [`write_line`].
+pub fn set_sink(sink: fn(&Record<'_>)) {
+    SINK.store(sink as *mut (), Ordering::Release);
+}
+
+pub(crate) fn writer() -> fn(fmt::Arguments<'_>) {
+    let writer = WRITER.load(Ordering::Acquire);
+    if writer.is_null() {
+        default_writer
+    } else {
+        // SAFETY: Only `set_writer` stores to `WRITER` and it stores a
+        // function pointer of this type.
+        unsafe { core::mem::transmute::<*mut (), fn(fmt::Arguments<'_>)>(writer) }
+    }
+}
+
+/// Returns the function each record is sent to.
+pub(crate) fn sink() -> fn(&Record<'_>) {
+    let sink = SINK.load(Ordering::Acquire);
+    if sink.is_null() {
+        write_line
+    } else {
+        // SAFETY: Only `set_sink` stores to `SINK` and it stores a function
+        // pointer of this type.
+        unsafe { core::mem::transmute::<*mut (), fn(&Record<'_>)>(sink) }
+    }
+}
+
+/// Keeps the record among the recent ones and sends it to a capture on this
+/// thread or else to the sink.
+fn dispatch(record: &Record<'_>) {
+    #[cfg(feature = "std")]
+    crate::recent::keep(record);
+    #[cfg(feature = "std")]
+    if crate::capture::keep(record) {
+        return;
+    }
+    sink()(record);
+}
+
+/// Returns true if lines go to stderr.
+#[cfg(feature = "std")]
+pub(crate) fn to_stderr() -> bool {
+    WRITER.load(Ordering::Relaxed).is_null() && !crate::file::from_env()
+}
+
+#[cfg(feature = "color")]
+fn color() -> bool {
+    crate::color::enabled(to_stderr())
+}
+
+/// Writes the record as a line to the [writer](set_writer), with color if
+/// enabled. This is the default sink.
+pub fn write_line(record: &Record<'_>) {
+    write_stamped(Stamp::now(), record);
+}
+
+/// Writes the record as a line with prefixes as of `stamp`.
+pub(crate) fn write_stamped(stamp: Stamp, record: &Record<'_>) {
+    let header = Header(stamp, record.location());
+    #[cfg(feature = "color")]
+    if color() {
+        use crate::color::{BOLD, NEW, OLD, RESET};
+        let header = PaddedHeader(header);
+        return match *record {
+            Record::Value { expr, value, .. } => writer()(format_args!(
+                "{} {BOLD}{}{RESET} = {NEW}{}{RESET}",
+                header, expr, value
+            )),
+            Record::Change {
+                expr,
+                index,
+                old,
+                new,
+                ..
+            } => writer()(format_args!(
+                "{} {BOLD}{}[{}]{RESET}: {OLD}{}{RESET} -> {NEW}{}{RESET}",
+                header,
+                expr,
+                index,
+                Elem(old),
+                Elem(new)
+            )),
+            Record::Location { .. } => writer()(format_args!("{}", header)),
+        };
+    }
+    match record {
+        Record::Location { .. } => writer()(format_args!("{}", header)),
+        _ => writer()(format_args!("{} {}", header, record)),
+    }
+}
+
+/// Writes `[prefix location]`.
+#[derive(Clone, Copy)]
+pub(crate) struct Header(pub(crate) Stamp, pub(crate) Location);
+
+impl fmt::Display for Header {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}{}]", self.0, self.1)
+    }
+}
+
+/// Writes the header dimmed and padded to line up with prior headers.
+#[cfg(feature = "color")]
+struct PaddedHeader(Header);
+
+#[cfg(feature = "color")]
+impl fmt::Display for PaddedHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use crate::color::{padding, DIM, RESET};
+        let header = self.0;
+        let mut width = Width(0);
+        fmt::write(&mut width, format_args!("{}", header))?;
+        write!(f, "{}{}{}{:pad$}", DIM, header, RESET, "", pad = padding(width.0))
+    }
+}
+
+/// Counts the characters written.
+#[cfg(feature = "color")]
+struct Width(usize);
+
+#[cfg(feature = "color")]
+impl fmt::Write for Width {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0 += s.chars().count();
+        Ok(())
+    }
+}
+
+/// Writes an element of a [`Record::Change`].
+struct Elem<'a>(Option<&'a dyn fmt::Debug>);
+
+impl fmt::Display for Elem<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Some(value) => write!(f, "{:?}", value),
+            None => f.write_str("(none)"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+fn default_writer(line: fmt::Arguments<'_>) {
+    if crate::file::from_env() {
+        crate::file::write_line(line);
+    } else {
+        std::eprintln!("{}", line);
+    }
+}
+
+#[cfg(not(feature = "std"))]
+fn default_writer(_line: fmt::Arguments<'_>) {}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! location {
+    () => {
+        $crate::write::Location {
+            file: ::core::file!(),
+            line: ::core::line!(),
+            column: ::core::column!(),
+        }
+    };
+}
+
+/// Writes `[location] expr = value` like [`std::dbg!`].
+#[doc(hidden)]
+pub fn dbg_value(site: &'static Site, value: Value<'_>, options: Options) {
+    if !site.is_enabled() {
+        return;
+    }
+    #[cfg(feature = "std")]
+    if !options.is_pretty() {
+        // The call site keeps the same line, so format the value once.
+        let line = std::string::ToString::to_string(&options.apply(value));
+        site.print(&line);
+        return dispatch_value(site, &line);
+    }
+    #[cfg(feature = "std")]
+    site.print(options.one_line().apply(value));
+    #[cfg(not(feature = "std"))]
+    site.print("");
+    dispatch_value(site, &options.apply(value));
+}
+
+fn dispatch_value(site: &Site, value: &dyn fmt::Display) {
+    dispatch(&Record::Value {
+        location: site.location(),
+        expr: site.expr(),
+        value,
+    });
+}
+
+/// Writes `[location] expr[index]: old -> new`.
+#[doc(hidden)]
+pub fn dbg_change<T: fmt::Debug>(site: &'static Site, change: &crate::filter::ElemChange<T>) {
+    if !site.is_enabled() {
+        return;
+    }
+    let record = Record::Change {
+        location: site.location(),
+        expr: site.expr(),
+        index: change.index,
+        old: change.old.as_ref().map(|v| v as &dyn fmt::Debug),
+        new: change.new.as_ref().map(|v| v as &dyn fmt::Debug),
+    };
+    site.print(record);
+    dispatch(&record);
+}
+
+/// Writes `[location]`.
+#[doc(hidden)]
+pub fn dbg_location(site: &'static Site) {
+    if !site.is_enabled() {
+        return;
+    }
+    site.print("");
+    dispatch(&Record::Location {
+        location: site.location(),
+    });
+}
+
+/// Sends each record to [`tracing`] as a `DEBUG` event with the target
+/// `dbg_if` and the fields `file`, `line`, `column`, `expr`, and `value` or
+/// `index`, `old`, and `new`.
+///
+/// ```rust
+/// dbg_if::write::set_sink(dbg_if::write::tracing_sink);
+/// ```
+#[cfg(feature = "tracing")]
+pub fn tracing_sink(record: &Record<'_>) {
+    use ::tracing::{event, Level};
+    match *record {
+        Record::Value {
+            location,
+            expr,
+            value,
+        } => event!(
+            target: "dbg_if",
+            Level::DEBUG,
+            file = location.file,
+            line = location.line,
+            column = location.column,
+            expr,
+            value = %value,
+            "{}",
+            record
+        ),
+        Record::Change {
+            location,
+            expr,
+            index,
+            old,
+            new,
+        } => event!(
+            target: "dbg_if",
+            Level::DEBUG,
+            file = location.file,
+            line = location.line,
+            column = location.column,
+            expr,
+            index,
+            old = %Elem(old),
+            new = %Elem(new),
+            "{}",
+            record
+        ),
+        Record::Location { location } => event!(
+            target: "dbg_if",
+            Level::DEBUG,
+            file = location.file,
+            line = location.line,
+            column = location.column,
+        ),
+    }
+}
+
+/// Sends each record to [`log`] at the `Debug` level with the target `dbg_if`,
+/// the file and line of the call site, and the key-values `column`, `expr`,
+/// and `value`, or `index`, `old`, and `new` for a change.
+///
+/// ```rust
+/// dbg_if::write::set_sink(dbg_if::write::log_sink);
+/// ```
+#[cfg(feature = "log")]
+pub fn log_sink(record: &Record<'_>) {
+    use ::log::{
+        kv::{Source, Value},
+        Level,
+    };
+    if Level::Debug > ::log::max_level() {
+        return;
+    }
+    let location = record.location();
+    let log = |key_values: &dyn Source| {
+        ::log::logger().log(
+            &::log::Record::builder()
+                .args(format_args!("{}", record))
+                .level(Level::Debug)
+                .target("dbg_if")
+                .file_static(Some(location.file))
+                .line(Some(location.line))
+                .key_values(key_values)
+                .build(),
+        )
+    };
+    let column = ("column", Value::from(location.column));
+    match *record {
+        Record::Value { expr, value, .. } => log(&[
+            column,
+            ("expr", Value::from(expr)),
+            ("value", Value::from_display(&value)),
+        ]),
+        Record::Change {
+            expr,
+            index,
+            old,
+            new,
+            ..
+        } => log(&[
+            column,
+            ("expr", Value::from(expr)),
+            ("index", Value::from(index)),
+            ("old", Value::from_display(&Elem(old))),
+            ("new", Value::from_display(&Elem(new))),
+        ]),
+        Record::Location { .. } => log(&[column, ("expr", Value::from(""))]),
+    }
+}
@@ -0,0 +1,113 @@
+#![cfg(feature = "std")]
+mod common;
+
+use common::{collect, collect_lines, line_count, take_lines};
+use dbg_if::{background, dbg_if_elems_ne, dbg_if_ne, file, write};
+use std::{
+    fmt, fs,
+    process::{Command, Stdio},
+    sync::{Mutex, MutexGuard, Once},
+};
+
+/// How many lines were written when the panic hook set before the
+/// background thread started ran.
+static AT_PANIC: Mutex<Option<usize>> = Mutex::new(None);
+
+/// Collects lines with the name of the thread that wrote them, and sets a
+/// panic hook before the background thread sets its own.
+fn start() -> MutexGuard<'static, ()> {
+    static HOOK: Once = Once::new();
+    let serial = collect_lines();
+    write::set_writer(collect_with_thread);
+    HOOK.call_once(|| {
+        std::panic::set_hook(Box::new(|_| {
+            *AT_PANIC.lock().unwrap() = Some(line_count());
+        }));
+    });
+    serial
+}
+
+fn collect_with_thread(line: fmt::Arguments<'_>) {
+    let thread = std::thread::current();
+    collect(format_args!("{}: {}", thread.name().unwrap_or("?"), line));
+}
+
+#[test]
+fn test_lines_written_on_background_thread() {
+    let _serial = start();
+    let guard = background::start();
+    fn f(x: u8, v: &[u8]) {
+        dbg_if_ne!(x, u8);
+        dbg_if_elems_ne!(v);
+    }
+    f(1, &[1]);
+    f(2, &[2]);
+    drop(guard);
+    assert_eq!(
+        take_lines(),
+        [
+            "dbg_if: [@] x = 1",
+            "dbg_if: [@] v = [\n    1,\n]",
+            "dbg_if: [@] x = 2",
+            "dbg_if: [@] v[0]: 1 -> 2",
+        ]
+    );
+}
+
+#[test]
+fn test_panic_writes_queued_lines_first() {
+    let _serial = start();
+    let _guard = background::start();
+    std::thread::spawn(|| {
+        dbg_if_ne!(3u8, u8);
+        panic!("boom");
+    })
+    .join()
+    .unwrap_err();
+    assert_eq!(*AT_PANIC.lock().unwrap(), Some(1));
+    assert_eq!(take_lines(), ["dbg_if: [@] 3u8 = 3"]);
+}
+
+#[cfg(any(unix, windows))]
+#[test]
+fn test_queued_lines_written_at_exit() {
+    if let Some(path) = std::env::var_os("DBG_IF_TEST_CHILD") {
+        let guard = background::start();
+        if !path.is_empty() {
+            // Opened after the background thread, so its file is flushed at
+            // exit before the queued lines would otherwise be written.
+            file::open(path, file::Rotation::default()).unwrap();
+        }
+        for i in 0..100u8 {
+            dbg_if_ne!(i, u8);
+        }
+        std::mem::forget(guard);
+        std::process::exit(0);
+    }
+
+    let run = |path: &std::path::Path| {
+        let output = Command::new(std::env::current_exe().unwrap())
+            .args([
+                "--exact",
+                "--nocapture",
+                "test_queued_lines_written_at_exit",
+            ])
+            .env("DBG_IF_TEST_CHILD", path)
+            .stdout(Stdio::null())
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stderr).unwrap()
+    };
+    let check = |log: &str| {
+        assert_eq!(log.lines().count(), 100, "{}", log);
+        assert!(log.ends_with("] i = 99\n"), "{}", log);
+    };
+
+    check(&run("".as_ref()));
+    let path = std::env::temp_dir().join(format!("dbg_if-background-{}.log", std::process::id()));
+    let _ = fs::remove_file(&path);
+    assert_eq!(run(&path), "");
+    check(&fs::read_to_string(&path).unwrap());
+    fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,65 @@
+//! Collects what the printing macros write for the tests that change global
+//! state.
+#![allow(dead_code)]
+use regex::Regex;
+use std::{
+    fmt,
+    sync::{Mutex, MutexGuard, OnceLock, PoisonError},
+};
+
+static LINES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+static SERIAL: Mutex<()> = Mutex::new(());
+
+/// Sets the writer to [`collect`] and clears the lines collected so far.
+///
+/// The writer is shared by the whole test binary, so tests hold the returned
+/// guard to run one at a time.
+pub fn collect_lines() -> MutexGuard<'static, ()> {
+    let serial = SERIAL.lock().unwrap_or_else(PoisonError::into_inner);
+    dbg_if::write::set_writer(collect);
+    take_lines();
+    serial
+}
+
+/// Keeps a line. This is the writer that [`collect_lines`] sets.
+pub fn collect(line: fmt::Arguments<'_>) {
+    lines().push(line.to_string());
+}
+
+/// Returns the lines collected since the last call, normalized.
+pub fn take_lines() -> Vec<String> {
+    std::mem::take(&mut *lines())
+        .iter()
+        .map(|line| normalize(line))
+        .collect()
+}
+
+/// Runs `f` the first time and returns the lines it wrote, normalized.
+///
+/// The call sites are listed for the whole test binary, so tests that check a
+/// table of them share one run of the code that reaches them. Each test binary
+/// has one such run.
+pub fn run_once(f: impl FnOnce()) -> &'static [String] {
+    static RUN: OnceLock<Vec<String>> = OnceLock::new();
+    RUN.get_or_init(|| {
+        f();
+        take_lines()
+    })
+}
+
+/// Returns how many lines were collected since [`take_lines`].
+pub fn line_count() -> usize {
+    lines().len()
+}
+
+/// Replaces locations in the tests and durations like `0.012s` with `@`.
+pub fn normalize(line: &str) -> String {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"tests/\w+\.rs:\d+:\d+|\d+\.\d+s").unwrap())
+        .replace_all(line, "@")
+        .into_owned()
+}
+
+fn lines() -> MutexGuard<'static, Vec<String>> {
+    LINES.lock().unwrap_or_else(PoisonError::into_inner)
+}
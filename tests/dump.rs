@@ -0,0 +1,69 @@
+#![cfg(feature = "std")]
+mod common;
+
+use common::{collect_lines, run_once, take_lines};
+use dbg_if::{dbg_if_ne, dbg_once, dump, recent, report};
+
+fn run() -> &'static [String] {
+    run_once(|| {
+        dump::install_panic_hook(2);
+        std::thread::spawn(|| {
+            dbg_once!();
+            for i in 0..=100u8 {
+                dbg_if_ne!(i / 40, u8);
+                assert!(i < 100);
+            }
+        })
+        .join()
+        .unwrap_err();
+    })
+}
+
+#[test]
+fn test_panic_keeps_last_observed() {
+    let _serial = collect_lines();
+    run();
+    let site = report::sites()
+        .find(|site| site.expr() == "i / 40")
+        .unwrap();
+    assert_eq!(site.last_observed().unwrap(), "2");
+}
+
+#[test]
+fn test_panic_writes_dump() {
+    let _serial = collect_lines();
+    assert_eq!(
+        run(),
+        [
+            "[@]",
+            "[@] i / 40 = 0",
+            "[@] i / 40 = 1",
+            "[@] i / 40 = 2",
+            "dbg_if: last values",
+            "location             expr    hits  prints  value",
+            "@             1       1  ",
+            "@  i / 40   101       3  2",
+            "dbg_if: recent records",
+            "[@] i / 40 = 1",
+            "[@] i / 40 = 2",
+        ]
+    );
+}
+
+#[test]
+fn test_panic_dumps_once_with_flight_recorder() {
+    let _serial = collect_lines();
+    run();
+    recent::flight_recorder(2);
+    dump::install_panic_hook(2);
+    std::thread::spawn(|| {
+        dbg_if_ne!(5u8, u8);
+        panic!("boom");
+    })
+    .join()
+    .unwrap_err();
+    let lines = take_lines();
+    let count = |expected: &str| lines.iter().filter(|line| *line == expected).count();
+    assert_eq!(count("dbg_if: last values"), 1, "{:?}", lines);
+    assert_eq!(count("[@] 5u8 = 5"), 1, "{:?}", lines);
+}
@@ -0,0 +1,34 @@
+#![cfg(feature = "std")]
+use dbg_if::{dbg_if_ne, file};
+use std::fs;
+
+#[test]
+fn test_file_rotates() {
+    let dir = std::env::temp_dir().join(format!("dbg_if-file-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("dbg.log");
+    file::open(
+        &path,
+        file::Rotation {
+            max_bytes: 100,
+            max_files: 2,
+        },
+    )
+    .unwrap();
+
+    for i in 0..20u8 {
+        dbg_if_ne!(i, u8);
+    }
+    file::flush();
+
+    let read = |name: &str| fs::read_to_string(dir.join(name)).unwrap();
+    let log = read("dbg.log");
+    assert!(log.ends_with("] i = 19\n"), "{}", log);
+    for name in ["dbg.log", "dbg.log.1", "dbg.log.2"] {
+        assert!(read(name).len() <= 100);
+    }
+    assert!(read("dbg.log.2").contains("] i = "));
+    assert!(!dir.join("dbg.log.3").exists());
+    fs::remove_dir_all(&dir).unwrap();
+}
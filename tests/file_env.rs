@@ -0,0 +1,38 @@
+#![cfg(feature = "std")]
+use dbg_if::dbg_if_ne;
+use std::{
+    fs,
+    process::{Command, Stdio},
+};
+
+#[test]
+fn test_file_from_env_flushed_at_exit() {
+    if std::env::var_os("DBG_IF_TEST_CHILD").is_some() {
+        for i in 0..5u8 {
+            dbg_if_ne!(i, u8);
+        }
+        // Exit without calling `file::flush`.
+        std::process::exit(0);
+    }
+
+    let path = std::env::temp_dir().join(format!("dbg_if-env-{}.log", std::process::id()));
+    let _ = fs::remove_file(&path);
+    let status = Command::new(std::env::current_exe().unwrap())
+        .args(["--exact", "test_file_from_env_flushed_at_exit"])
+        .env("DBG_IF_TEST_CHILD", "1")
+        // Read once, by the first line the child writes.
+        .env("DBG_IF_FILE", &path)
+        .stdout(Stdio::null())
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let log = fs::read_to_string(&path).unwrap();
+    let lines: Vec<_> = log.lines().collect();
+    assert_eq!(lines.len(), 5, "{}", log);
+    for (i, line) in lines.iter().enumerate() {
+        assert!(line.starts_with("[tests/file_env.rs:"), "{}", log);
+        assert!(line.ends_with(&format!("] i = {}", i)), "{}", log);
+    }
+    fs::remove_file(&path).unwrap();
+}
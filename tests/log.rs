@@ -0,0 +1,61 @@
+#![cfg(all(feature = "log", feature = "alloc"))]
+use dbg_if::{dbg_if_elems_ne, dbg_if_ne, dbg_once, write};
+use log::{kv::Key, Log, Metadata, Record};
+use std::sync::Mutex;
+
+static LINES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+struct Logger;
+
+impl Log for Logger {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        let mut key_values = String::new();
+        for key in ["expr", "value", "index", "old", "new"] {
+            if let Some(value) = record.key_values().get(Key::from(key)) {
+                key_values += &format!("{}={} ", key, value);
+            }
+        }
+        LINES.lock().unwrap().push(format!(
+            "{} {} {}:{} {}{}",
+            record.level(),
+            record.target(),
+            record.file().unwrap(),
+            record.line().unwrap() > 0,
+            key_values,
+            record.args()
+        ));
+    }
+
+    fn flush(&self) {}
+}
+
+#[test]
+fn test_log_sink() {
+    log::set_logger(&Logger).unwrap();
+    log::set_max_level(log::LevelFilter::Debug);
+    write::set_sink(write::log_sink);
+
+    fn f(x: u8, v: &[u8]) {
+        dbg_if_ne!(x, u8);
+        dbg_if_elems_ne!(v);
+    }
+    f(1, &[1]);
+    f(1, &[2]);
+    f(2, &[2]);
+    dbg_once!();
+
+    assert_eq!(
+        *LINES.lock().unwrap(),
+        [
+            "DEBUG dbg_if tests/log.rs:true expr=x value=1 x = 1",
+            "DEBUG dbg_if tests/log.rs:true expr=v value=[\n    1,\n] v = [\n    1,\n]",
+            "DEBUG dbg_if tests/log.rs:true expr=v index=0 old=1 new=2 v[0]: 1 -> 2",
+            "DEBUG dbg_if tests/log.rs:true expr=x value=2 x = 2",
+            "DEBUG dbg_if tests/log.rs:true expr= ",
+        ]
+    );
+}
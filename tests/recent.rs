@@ -0,0 +1,57 @@
+#![cfg(feature = "std")]
+mod common;
+
+use common::{collect_lines, take_lines};
+use dbg_if::{dbg_if_ne, dump_recent, recent};
+use std::sync::MutexGuard;
+
+/// Turns on the flight recorder with no records kept yet. Each test turns it
+/// on again, which sets its panic hook only once.
+fn flight_recorder() -> MutexGuard<'static, ()> {
+    let serial = collect_lines();
+    recent::set_capacity(0);
+    recent::flight_recorder(3);
+    serial
+}
+
+#[test]
+fn test_no_output_while_recorder_is_on() {
+    let _serial = flight_recorder();
+    for x in 0..5u8 {
+        dbg_if_ne!(x, u8);
+    }
+    assert!(take_lines().is_empty());
+    let values: Vec<_> = recent::recent(10).into_iter().map(|r| r.value).collect();
+    assert_eq!(values, ["2", "3", "4"]);
+}
+
+#[test]
+fn test_dump_recent_writes_last_n() {
+    let _serial = flight_recorder();
+    for x in 0..5u8 {
+        dbg_if_ne!(x, u8);
+    }
+    dump_recent(2);
+    assert_eq!(take_lines(), ["[@] x = 3", "[@] x = 4"]);
+}
+
+#[test]
+fn test_panic_writes_recent_records() {
+    let _serial = flight_recorder();
+    dbg_if_ne!(1u8, u8);
+    std::thread::spawn(|| {
+        dbg_if_ne!(2u8, u8);
+        panic!("boom");
+    })
+    .join()
+    .unwrap_err();
+    assert_eq!(take_lines(), ["[@] 1u8 = 1", "[@] 2u8 = 2"]);
+}
+
+#[test]
+fn test_zero_capacity_keeps_none() {
+    let _serial = flight_recorder();
+    dbg_if_ne!(1u8, u8);
+    recent::set_capacity(0);
+    assert!(recent::recent(10).is_empty());
+}
@@ -0,0 +1,48 @@
+#![cfg(feature = "std")]
+mod common;
+
+use common::{collect_lines, run_once, take_lines};
+use dbg_if::{dbg_if_elems_ne, dbg_if_ne, dbg_once, report};
+
+fn run() {
+    run_once(|| {
+        report::keep_last();
+        fn f(x: u8, v: &[u8]) {
+            dbg_if_ne!(x / 2, u8);
+            dbg_if_elems_ne!(v);
+        }
+        for x in 0..5 {
+            f(x, &[x / 4]);
+        }
+        for _ in 0..3 {
+            dbg_once!((1, "a"));
+        }
+    });
+}
+
+#[test]
+fn test_site_counts_and_last_value() {
+    let _serial = collect_lines();
+    run();
+    let site = report::sites().find(|site| site.expr() == "x / 2").unwrap();
+    assert_eq!((site.hits(), site.prints()), (5, 3));
+    assert_eq!(site.last_value().unwrap(), "2");
+    assert!(site.last_changed().is_some());
+}
+
+#[test]
+fn test_report_on_drop_writes_table() {
+    let _serial = collect_lines();
+    run();
+    take_lines();
+    drop(report::report_on_drop());
+    assert_eq!(
+        take_lines(),
+        [
+            "location               expr      hits  prints  last          changed",
+            "@  x / 2        5       3  2             +@",
+            "@  v            5       2  v[0]: 0 -> 1  +@",
+            "@  (1, \"a\")     3       1  (1, \"a\")      +@",
+        ]
+    );
+}
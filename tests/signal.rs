@@ -0,0 +1,32 @@
+#![cfg(all(feature = "signal", unix))]
+mod common;
+
+use common::{collect_lines, line_count, take_lines};
+use dbg_if::{dbg_if_ne, recent, signal};
+use std::time::{Duration, Instant};
+
+#[test]
+fn test_report_on_sigusr1() {
+    let _serial = collect_lines();
+    recent::flight_recorder(1);
+    signal::report_on_sigusr1().unwrap();
+    for x in 0..3u8 {
+        dbg_if_ne!(x, u8);
+    }
+
+    signal_hook::low_level::raise(signal_hook::consts::SIGUSR1).unwrap();
+    let start = Instant::now();
+    while line_count() < 4 && start.elapsed() < Duration::from_secs(5) {
+        std::thread::sleep(Duration::from_millis(10));
+    }
+
+    assert_eq!(
+        take_lines(),
+        [
+            "location              expr  hits  prints  last  changed",
+            "@  x        3       3  2     +@",
+            "dbg_if: recent records",
+            "[@] x = 2",
+        ]
+    );
+}
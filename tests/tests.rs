@@ -44,6 +44,102 @@ mod test_output {
         assert_eq!(&output[..], "\"hi\" = \"hi\"");
     }
 
+    #[ignore]
+    #[test]
+    fn test_dbg_once_eval_once() {
+        fn f(x: &mut usize) -> usize {
+            dbg_once!({
+                *x += 1;
+                *x
+            })
+        }
+
+        let mut x: usize = 0;
+        let output = strip_dbg(capture_stderr(|| {
+            assert_eq!(f(&mut x), 1);
+            assert_eq!(f(&mut x), 2);
+        }));
+        assert_eq!(x, 2);
+        assert_eq!(&output[..], "{ *x += 1; *x } = 1");
+    }
+
+    #[ignore]
+    #[test]
+    fn test_dbg_once_multiple() {
+        fn f(x: usize, y: u64) -> (usize, u64) {
+            dbg_once!(x, y)
+        }
+
+        let output = strip_dbg(capture_stderr(|| {
+            assert_eq!(f(1, 3), (1, 3));
+            assert_eq!(f(2, 3), (2, 3));
+        }));
+        assert_eq!(&output[..], "x = 1\ny = 3");
+    }
+
+    #[ignore]
+    #[test]
+    fn test_dbg_once_no_args() {
+        fn f() {
+            dbg_once!();
+        }
+
+        let output = capture_stderr(|| {
+            f();
+            f();
+        });
+        assert_eq!(output.lines().count(), 1);
+        assert!(output.starts_with("[tests/tests.rs:"));
+    }
+
+    #[ignore]
+    #[test]
+    fn test_prefix() {
+        use dbg_if::prefix::{set_prefix, Prefix};
+        use regex::Regex;
+
+        let output = capture_stderr(|| {
+            set_tick(7);
+            dbg_once!(1);
+            set_prefix(Prefix {
+                elapsed: true,
+                ..Prefix::default()
+            });
+            dbg_once!(2);
+            set_prefix(Prefix::default());
+            dbg_once!(3);
+        });
+        let r = Regex::new(
+            r"^\[#7 tests/tests.rs:\d+:\d+\] 1 = 1
+\[\+\d+\.\d{3}s tests/tests.rs:\d+:\d+\] 2 = 2
+\[tests/tests.rs:\d+:\d+\] 3 = 3
+$",
+        )
+        .unwrap();
+        assert!(r.is_match(&output), "{}", output);
+    }
+
+    #[ignore]
+    #[test]
+    fn test_prefix_elapsed_from_first_macro() {
+        use dbg_if::prefix::{set_prefix, Prefix};
+        use regex::Regex;
+
+        let output = capture_stderr(|| {
+            dbg_once!(1);
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            set_prefix(Prefix {
+                elapsed: true,
+                ..Prefix::default()
+            });
+            dbg_once!(2);
+            set_prefix(Prefix::default());
+        });
+        let elapsed = Regex::new(r"\[\+(\d+\.\d{3})s ").unwrap();
+        let elapsed: f64 = elapsed.captures(&output).unwrap()[1].parse().unwrap();
+        assert!(elapsed >= 0.05, "{}", output);
+    }
+
     #[ignore]
     #[test]
     fn test_dbg_if_hash_ne() {
@@ -106,6 +202,185 @@ mod test_output {
         assert_eq!(&output[..], "x = 1\nx = 2");
     }
 
+    #[ignore]
+    #[test]
+    fn test_dbg_if_ne_hysteresis() {
+        fn f(x: i32) {
+            dbg_if_ne!(x, i32, hysteresis_ne_args!(5, 10));
+        }
+
+        let output = strip_dbg(capture_stderr(|| {
+            for x in [11, 12, 9, 11, 6, 4, 5, 3, 10, 11] {
+                f(x);
+            }
+        }));
+        assert_eq!(&output[..], "x = 11\nx = 4\nx = 11");
+    }
+
+    #[ignore]
+    #[test]
+    fn test_dbg_if_ne_delta() {
+        fn f(x: i8) {
+            dbg_if_ne!(x, i8, delta_ne_args!(min = 100));
+        }
+
+        let output = strip_dbg(capture_stderr(|| {
+            for x in [0, 99, -99, -100, 127, 28, 27] {
+                f(x);
+            }
+        }));
+        assert_eq!(&output[..], "x = 0\nx = -100\nx = 127\nx = 27");
+    }
+
+    #[ignore]
+    #[test]
+    fn test_dbg_if_ne_percent() {
+        fn f(x: usize) {
+            dbg_if_ne!(x, usize, percent_ne_args!(10));
+        }
+
+        let output = strip_dbg(capture_stderr(|| {
+            for x in [0, 0, 5, 5, 6, 100, 109, 91, 90] {
+                f(x);
+            }
+        }));
+        assert_eq!(&output[..], "x = 0\nx = 5\nx = 6\nx = 100\nx = 90");
+    }
+
+    #[cfg(feature = "alloc")]
+    #[ignore]
+    #[test]
+    fn test_dbg_if_elems_ne() {
+        fn f(v: &[u8]) {
+            dbg_if_elems_ne!(v);
+        }
+
+        let output = strip_dbg(capture_stderr(|| {
+            f(&[1, 2]);
+            f(&[1, 2]);
+            f(&[3, 2, 4]);
+            f(&[3]);
+        }));
+        assert_eq!(
+            &output[..],
+            "v = [\n    1,\n    2,\n]\nv[0]: 1 -> 3\nv[2]: (none) -> 4\nv[1]: 2 -> (none)\nv[2]: 4 -> (none)"
+        );
+    }
+
+    #[cfg(feature = "alloc")]
+    #[ignore]
+    #[test]
+    fn test_dbg_if_elems_ne_pass_thru() {
+        fn f(v: Vec<String>) -> Vec<String> {
+            dbg_if_elems_ne!(v)
+        }
+
+        let output = strip_dbg(capture_stderr(|| {
+            let v = f(vec!["a".into()]);
+            let mut v = f(v);
+            v.push("b".into());
+            assert_eq!(f(v), ["a", "b"]);
+        }));
+        assert_eq!(&output[..], "v = [\n    \"a\",\n]\nv[1]: (none) -> \"b\"");
+    }
+
+    #[ignore]
+    #[test]
+    fn test_dbg_when() {
+        fn f(x: u32) -> u32 {
+            dbg_when!(x, |v| *v > 100)
+        }
+
+        let output = strip_dbg(capture_stderr(|| {
+            assert_eq!(f(1), 1);
+            assert_eq!(f(101), 101);
+            assert_eq!(f(2), 2);
+            assert_eq!(f(102), 102);
+        }));
+        assert_eq!(&output[..], "x = 101\nx = 102");
+    }
+
+    #[ignore]
+    #[test]
+    fn test_dbg_when_once() {
+        fn f(x: u32) {
+            dbg_when!(x, |v| *v > 100, Once);
+        }
+
+        let output = strip_dbg(capture_stderr(|| {
+            f(1);
+            f(101);
+            f(102);
+        }));
+        assert_eq!(&output[..], "x = 101");
+    }
+
+    #[ignore]
+    #[test]
+    fn test_dbg_when_if_ne() {
+        fn f(x: u32) {
+            dbg_when!(x, |v| *v > 100, IfNe, u32);
+        }
+
+        let output = strip_dbg(capture_stderr(|| {
+            f(101);
+            f(1);
+            f(101);
+            f(102);
+        }));
+        assert_eq!(&output[..], "x = 101\nx = 102");
+    }
+
+    #[ignore]
+    #[test]
+    fn test_dbg_with() {
+        struct Even;
+
+        impl Even {
+            const fn new() -> Self {
+                Even
+            }
+        }
+
+        impl DbgFilter<u32> for Even {
+            fn should_print(&self, value: &u32) -> bool {
+                value.is_multiple_of(2)
+            }
+        }
+
+        fn f(x: u32) -> u32 {
+            dbg_with!(x, Even)
+        }
+
+        let output = strip_dbg(capture_stderr(|| {
+            for x in 1..=4 {
+                assert_eq!(f(x), x);
+            }
+        }));
+        assert_eq!(&output[..], "x = 2\nx = 4");
+    }
+
+    #[ignore]
+    #[test]
+    fn test_dbg_with_eval_once() {
+        fn f(x: &mut usize) {
+            dbg_with!(
+                {
+                    *x += 1;
+                    *x
+                },
+                IfNe(usize)
+            );
+        }
+
+        let mut x: usize = 0;
+        let output = strip_dbg(capture_stderr(|| {
+            f(&mut x);
+        }));
+        assert_eq!(x, 1);
+        assert_eq!(&output[..], "{ *x += 1; *x } = 1");
+    }
+
     #[ignore]
     #[test]
     fn test_pass_thru() {
@@ -215,6 +490,171 @@ mod test_output_dbg_if {
         assert_eq!(&output[..], "x = 1\nx = 2");
     }
 
+    #[ignore]
+    #[test]
+    fn test_dbg_when() {
+        fn f(x: u32) {
+            dbg!(x, When(|v| *v > 100));
+        }
+
+        let output = strip_dbg(capture_stderr(|| {
+            f(1);
+            f(101);
+            f(102);
+        }));
+        assert_eq!(&output[..], "x = 101\nx = 102");
+    }
+
+    #[ignore]
+    #[test]
+    fn test_dbg_when_once() {
+        fn f(x: u32) {
+            dbg!(x, When(|v| *v > 100), Once);
+        }
+
+        let output = strip_dbg(capture_stderr(|| {
+            f(1);
+            f(101);
+            f(102);
+        }));
+        assert_eq!(&output[..], "x = 101");
+    }
+
+    #[ignore]
+    #[test]
+    fn test_filter_and() {
+        fn f(x: u8) {
+            dbg!(x, IfNe(u8) & EveryN(2));
+        }
+
+        let output = strip_dbg(capture_stderr(|| {
+            for x in [1, 1, 2, 3, 3, 4] {
+                f(x);
+            }
+        }));
+        assert_eq!(&output[..], "x = 1\nx = 3");
+    }
+
+    #[ignore]
+    #[test]
+    fn test_filter_or() {
+        fn f(x: u8) {
+            dbg!(x, Once | When(|v| *v > 5));
+        }
+
+        let output = strip_dbg(capture_stderr(|| {
+            for x in [1, 2, 6, 3, 7] {
+                f(x);
+            }
+        }));
+        assert_eq!(&output[..], "x = 1\nx = 6\nx = 7");
+    }
+
+    #[ignore]
+    #[test]
+    fn test_filter_precedence() {
+        fn f(x: u8) {
+            dbg!(x, When(|v| *v == 0) | When(|v| *v > 5) & Once);
+            dbg!(x, (When(|v| *v == 0) | When(|v| *v > 5)) & Once);
+        }
+
+        let output = strip_dbg(capture_stderr(|| {
+            for x in [0, 0, 6, 7] {
+                f(x);
+            }
+        }));
+        assert_eq!(&output[..], "x = 0\nx = 0\nx = 0\nx = 6");
+    }
+
+    #[ignore]
+    #[test]
+    fn test_filter_if_ne_closure() {
+        fn f(x: i8) {
+            dbg!(x, IfNe(i8, |a: i8, b: i8| (a - b).abs() >= 10));
+        }
+
+        let output = strip_dbg(capture_stderr(|| {
+            for x in [0, 5, 10, 12, 21] {
+                f(x);
+            }
+        }));
+        assert_eq!(&output[..], "x = 0\nx = 10\nx = 21");
+    }
+
+    #[ignore]
+    #[test]
+    fn test_filter_custom() {
+        use dbg_if::DbgFilter;
+
+        struct Odd;
+
+        impl Odd {
+            const fn new() -> Self {
+                Odd
+            }
+        }
+
+        impl DbgFilter<u8> for Odd {
+            fn should_print(&self, value: &u8) -> bool {
+                value % 2 == 1
+            }
+        }
+
+        fn f(x: u8) {
+            dbg!(x, Odd & IfNe(u8));
+        }
+
+        let output = strip_dbg(capture_stderr(|| {
+            for x in [1, 1, 2, 3] {
+                f(x);
+            }
+        }));
+        assert_eq!(&output[..], "x = 1\nx = 3");
+    }
+
+    #[ignore]
+    #[test]
+    fn test_format() {
+        #[allow(dead_code)]
+        #[derive(Debug)]
+        struct P {
+            x: u8,
+            y: u8,
+        }
+
+        let output = strip_dbg(capture_stderr(|| {
+            dbg!(P { x: 1, y: 2 }; Compact);
+            dbg!((1, 2), Once; Display);
+            dbg!("hello", Every; Display, max_len = 3);
+            dbg!("hello"; max_len = 10);
+            dbg!("hello"; max_len = 7);
+        }));
+        assert_eq!(
+            &output[..],
+            "P { x: 1, y: 2 } = P { x: 1, y: 2 }\n\
+             (1, 2) = (1, 2)\n\
+             \"hello\" = hel…\n\
+             \"hello\" = \"hello\"\n\
+             \"hello\" = \"hello\""
+        );
+    }
+
+    #[ignore]
+    #[test]
+    fn test_set_format() {
+        use dbg_if::format::{set_format, set_max_len, Format};
+
+        let output = strip_dbg(capture_stderr(|| {
+            set_format(Format::Compact);
+            set_max_len(Some(4));
+            dbg!((1, 2, 3));
+            dbg!((1, 2); Pretty, max_len = 0);
+            set_format(Format::Pretty);
+            set_max_len(None);
+        }));
+        assert_eq!(&output[..], "(1, 2, 3) = (1, …\n(1, 2) = (\n    1,\n    2,\n)");
+    }
+
     #[ignore]
     #[test]
     fn test_pass_thru() {
@@ -230,6 +670,257 @@ mod test_output_dbg_if {
     }
 }
 
+#[cfg(feature = "std")]
+mod capture_tests {
+    use dbg_if::{capture::Capture, *};
+
+    fn f(x: u8) -> u8 {
+        dbg_if_ne!(x, u8)
+    }
+
+    #[test]
+    fn test_capture() {
+        let records = capture(|| {
+            for x in [1, 1, 2] {
+                f(x);
+            }
+        });
+        let lines: Vec<_> = records.iter().map(|r| r.to_string()).collect();
+        assert_eq!(lines, ["x = 1", "x = 2"]);
+        assert_eq!(records[0].location.file, "tests/tests.rs");
+        assert_eq!(records[0].expr, "x");
+        assert_eq!(records[0].value, "1");
+    }
+
+    #[test]
+    fn test_capture_is_per_thread() {
+        let records = capture(|| {
+            std::thread::spawn(|| {
+                dbg_once!("other thread");
+            })
+            .join()
+            .unwrap();
+            dbg_once!("this thread");
+        });
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].value, "\"this thread\"");
+    }
+
+    #[test]
+    fn test_capture_elems() {
+        fn g(v: &[u8]) {
+            dbg_if_elems_ne!(v);
+        }
+        let records = capture(|| {
+            g(&[1]);
+            g(&[2, 3]);
+            dbg_once!();
+        });
+        let lines: Vec<_> = records.iter().map(|r| r.to_string()).collect();
+        assert_eq!(lines, ["v = [\n    1,\n]", "v[0]: 1 -> 2", "v[1]: (none) -> 3", ""]);
+        assert_eq!(records[1].old.as_deref(), Some("1"));
+    }
+
+    #[test]
+    fn test_capture_nested() {
+        let outer = Capture::start();
+        dbg_if!(1);
+        let inner = capture(|| {
+            dbg_if!(2);
+        });
+        dbg_if!(3);
+        assert_eq!(outer.records().len(), 2);
+        let outer = outer.finish();
+        assert_eq!(inner.len(), 1);
+        assert_eq!(inner[0].value, "2");
+        assert_eq!(outer[1].value, "3");
+    }
+
+    #[test]
+    fn test_capture_dropped_out_of_order() {
+        let outer = Capture::start();
+        let inner = Capture::start();
+        dbg_if!(1);
+        drop(outer); // Ends `inner` too.
+        assert!(inner.records().is_empty());
+        let other = Capture::start();
+        dbg_if!(2);
+        assert!(inner.finish().is_empty());
+        assert_eq!(other.finish()[0].value, "2");
+    }
+
+    #[test]
+    fn test_capture_formats_once() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        static FORMATTED: AtomicUsize = AtomicUsize::new(0);
+        struct Counted;
+        impl std::fmt::Debug for Counted {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                FORMATTED.fetch_add(1, Ordering::Relaxed);
+                f.write_str("Counted")
+            }
+        }
+        let records = capture(|| {
+            dbg_if!(Counted; Compact);
+        });
+        assert_eq!(records[0].value, "Counted");
+        assert_eq!(FORMATTED.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_capture_formats_pretty_once() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        static FORMATTED: AtomicUsize = AtomicUsize::new(0);
+        struct Counted;
+        impl std::fmt::Debug for Counted {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                FORMATTED.fetch_add(1, Ordering::Relaxed);
+                f.write_str("Counted")
+            }
+        }
+        let records = capture(|| {
+            dbg_once!(Counted);
+        });
+        assert_eq!(records[0].value, "Counted");
+        assert_eq!(FORMATTED.load(Ordering::Relaxed), 1);
+    }
+}
+
+#[cfg(feature = "std")]
+mod scope_tests {
+    use dbg_if::*;
+
+    fn f(x: u8) -> bool {
+        was_ne!(x, u8)
+    }
+
+    fn g(x: u8) -> u8 {
+        dbg_if!(x, IfNe(u8) & EveryN(1))
+    }
+
+    #[test]
+    fn test_scope() {
+        let _scope = DbgScope::new();
+        assert!(f(1));
+        assert!(!f(1));
+        {
+            let _inner = DbgScope::new();
+            assert!(f(1));
+        }
+        assert!(!f(1));
+    }
+
+    #[test]
+    fn test_scope_dropped_out_of_order() {
+        fn h(x: u8) -> bool {
+            was_ne!(x, u8)
+        }
+        let _scope = DbgScope::new();
+        assert!(h(1));
+        let outer = DbgScope::new();
+        assert!(h(2));
+        let inner = DbgScope::new();
+        drop(outer); // Ends `inner` too.
+        assert!(h(2));
+        drop(inner);
+        assert!(!h(2));
+    }
+
+    #[test]
+    fn test_scope_threads() {
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                std::thread::spawn(|| {
+                    let _scope = DbgScope::new();
+                    (0..100).filter(|_| f(7)).count()
+                })
+            })
+            .collect();
+        for thread in threads {
+            assert_eq!(thread.join().unwrap(), 1);
+        }
+    }
+
+    #[test]
+    fn test_scope_capture() {
+        for _ in 0..2 {
+            let _scope = DbgScope::new();
+            let records = capture(|| {
+                g(1);
+                g(1);
+                g(2);
+            });
+            let values: Vec<_> = records.iter().map(|r| r.value.as_str()).collect();
+            assert_eq!(values, ["1", "2"]);
+        }
+    }
+
+    #[test]
+    fn test_scope_elems_and_hysteresis() {
+        fn h(v: &[u8]) -> bool {
+            was_elems_ne!(v)
+        }
+        fn k(x: u8) -> bool {
+            was_ne!(x, u8, hysteresis_ne_args!(5, 10))
+        }
+        for _ in 0..2 {
+            let _scope = DbgScope::new();
+            assert!(h(&[1]));
+            assert!(!h(&[1]));
+            assert!(k(11));
+            assert!(!k(8));
+            assert!(k(4));
+        }
+    }
+}
+
+#[cfg(feature = "color")]
+mod color_tests {
+    use super::*;
+    use dbg_if::{
+        color::{set_color, Color},
+        *,
+    };
+
+    fn strip_location(input: String) -> String {
+        use regex::Regex;
+        let r = Regex::new(r"\x1b\[2m\[.*?\]\x1b\[0m *").unwrap();
+        r.replace_all(input.trim(), "").to_string()
+    }
+
+    #[ignore]
+    #[test]
+    fn test_color() {
+        fn f(v: &[u8]) -> u8 {
+            dbg_if_elems_ne!(v);
+            dbg_once!(v[0])
+        }
+
+        let output = capture_stderr(|| {
+            set_color(Color::Always);
+            f(&[1, 2]);
+            f(&[1, 3]);
+            set_color(Color::Auto);
+        });
+        assert_eq!(
+            strip_location(output),
+            "\x1b[1mv\x1b[0m = \x1b[32m[\n    1,\n    2,\n]\x1b[0m\n\
+             \x1b[1mv[0]\x1b[0m = \x1b[32m1\x1b[0m\n\
+             \x1b[1mv[1]\x1b[0m: \x1b[9;31m2\x1b[0m -> \x1b[32m3\x1b[0m"
+        );
+    }
+
+    #[ignore]
+    #[test]
+    fn test_color_auto() {
+        // Captured stderr is not a terminal.
+        let output = strip_dbg(capture_stderr(|| {
+            dbg_once!(1);
+        }));
+        assert_eq!(&output[..], "1 = 1");
+    }
+}
+
 #[cfg(feature = "float")]
 mod float_tests {
     use super::*;
@@ -293,6 +984,163 @@ mod float_tests {
         assert_eq!(&output[..], "x = 1.1\nx = 1.2");
     }
 
+    #[test]
+    fn test_dbg_if_ne_f32_hysteresis() {
+        fn f(x: f32) {
+            dbg_if_ne!(x, f32, hysteresis_ne_args!(-0.5, 0.5));
+        }
+
+        let output = strip_dbg(capture_stderr(|| {
+            for x in [0.0, 0.4, 0.6, 0.1, 0.7, -0.4, -0.6] {
+                f(x);
+            }
+        }));
+        assert_eq!(&output[..], "x = 0.0\nx = 0.6\nx = -0.6");
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_dbg_if_elems_ne_relative() {
+        fn f(v: [f32; 3]) {
+            dbg_if_elems_ne!(v, relative_ne_args!(epsilon = 0.1));
+        }
+
+        let output = strip_dbg(capture_stderr(|| {
+            f([1.0, 2.0, 3.0]);
+            f([1.05, 2.0, 3.5]);
+        }));
+        assert_eq!(
+            &output[..],
+            "v = [\n    1.0,\n    2.0,\n    3.0,\n]\nv[2]: 3.0 -> 3.5"
+        );
+    }
+
+    #[test]
+    fn test_dbg_if_ne_array() {
+        fn f(x: [f32; 3]) {
+            dbg_if_ne!(x, [f32; 3], abs_diff_ne_args!(epsilon = 0.5));
+        }
+
+        let output = strip_dbg(capture_stderr(|| {
+            f([0.0, 0.0, 0.0]);
+            f([0.1, 0.2, 0.3]);
+            f([0.1, 0.2, 0.6]);
+        }));
+        assert_eq!(
+            &output[..],
+            "x = [\n    0.0,\n    0.0,\n    0.0,\n]\nx = [\n    0.1,\n    0.2,\n    0.6,\n]"
+        );
+    }
+
+    #[test]
+    fn test_dbg_if_ne_tuple() {
+        fn f(x: (f64, f64)) {
+            dbg_if_ne!(x, (f64, f64), relative_ne_args!(max_relative = 0.1));
+        }
+
+        let output = strip_dbg(capture_stderr(|| {
+            f((1.0, 2.0));
+            f((1.05, 2.0));
+            f((1.05, 2.5));
+        }));
+        assert_eq!(
+            &output[..],
+            "x = (\n    1.0,\n    2.0,\n)\nx = (\n    1.05,\n    2.5,\n)"
+        );
+    }
+
+    #[test]
+    fn test_dbg_if_ne_abs_diff_eq_type() {
+        #[derive(Debug, Clone, Copy, Default, PartialEq)]
+        struct Vec2 {
+            x: f32,
+            y: f32,
+        }
+
+        impl approx::AbsDiffEq for Vec2 {
+            type Epsilon = f32;
+
+            fn default_epsilon() -> f32 {
+                f32::EPSILON
+            }
+
+            fn abs_diff_eq(&self, other: &Self, epsilon: f32) -> bool {
+                self.x.abs_diff_eq(&other.x, epsilon) && self.y.abs_diff_eq(&other.y, epsilon)
+            }
+        }
+
+        fn f(v: Vec2) -> bool {
+            was_ne!(v, Vec2, abs_diff_ne_args!(epsilon = 0.5))
+        }
+
+        assert!(f(Vec2 { x: 0.0, y: 0.0 }));
+        assert!(!f(Vec2 { x: 0.4, y: 0.0 }));
+        assert!(f(Vec2 { x: 0.4, y: 0.6 }));
+    }
+
+    #[test]
+    fn test_ulps_ne_quaternion() {
+        fn f(q: [f32; 4]) -> bool {
+            was_ne!(q, [f32; 4], ulps_ne_args!())
+        }
+
+        assert!(f([0.0, 0.0, 0.0, 1.0]));
+        assert!(!f([0.0, 0.0, 0.0, 1.0]));
+        assert!(f([0.0, 0.0, 0.5, 0.5]));
+    }
+
+    #[test]
+    fn test_dbg_if_ne_nan() {
+        fn f(x: f32) {
+            dbg_if_ne!(x, f32);
+        }
+
+        let output = strip_dbg(capture_stderr(|| {
+            for x in [1.0, f32::NAN, f32::NAN, -f32::NAN, 2.0] {
+                f(x);
+            }
+        }));
+        assert_eq!(&output[..], "x = 1.0\nx = NaN\nx = 2.0");
+    }
+
+    #[test]
+    fn test_was_ne_signed_zero() {
+        fn f(x: f64) -> bool {
+            was_ne!(x, f64)
+        }
+
+        assert!(f(0.0));
+        assert!(!f(-0.0));
+        assert!(!f(0.0));
+        assert!(f(f64::MIN_POSITIVE));
+    }
+
+    #[test]
+    fn test_dbg_if_ne_nan_loud() {
+        fn f(x: f32) {
+            dbg_if_ne!(x, f32, nan_ne_args!(hysteresis_ne_args!(-1.0, 1.0)));
+        }
+
+        let output = strip_dbg(capture_stderr(|| {
+            for x in [0.0, f32::NAN, f32::NAN, 0.5, 2.0] {
+                f(x);
+            }
+        }));
+        assert_eq!(&output[..], "x = 0.0\nx = NaN\nx = 0.5\nx = 2.0");
+    }
+
+    #[test]
+    fn test_was_ne_nan_relative() {
+        fn f(x: f64) -> bool {
+            was_ne!(x, f64, nan_ne_args!(relative_ne_args!()))
+        }
+
+        assert!(f(f64::NAN));
+        assert!(!f(f64::NAN));
+        assert!(f(1.0));
+        assert!(!f(1.0));
+    }
+
     #[test]
     fn test_dbg_if_ne_f64() {
         fn f(x: f64) {
@@ -421,3 +1269,40 @@ mod nocapture {
         }
     }
 }
+
+#[cfg(all(feature = "debug_only", not(debug_assertions)))]
+mod debug_only {
+    use super::*;
+    use dbg_if::*;
+
+    #[test]
+    fn test_debug_only_was_is_false() {
+        for _ in 0..2 {
+            assert!(!once!());
+            assert!(!was_ne!(1, u8));
+            assert!(!was_hash_ne!("hello"));
+            assert!(!was_elems_ne!([1, 2]));
+            assert!(!when!(1, |_| true));
+            assert!(!with!(1, Every));
+        }
+    }
+
+    #[ignore]
+    #[test]
+    fn test_debug_only_dbg_is_pass_thru() {
+        let mut count = 0;
+        let mut inc = || {
+            count += 1;
+            count
+        };
+        let output = capture_stderr(|| {
+            assert_eq!(dbg_once!(inc()), 1);
+            assert_eq!(dbg_if_ne!(inc(), u8), 2);
+            assert_eq!(dbg_if_hash_ne!(inc()), 3);
+            assert_eq!(dbg_if!(inc(), Every), 4);
+            assert_eq!(dbg_if_elems_ne!([inc()]), [5]);
+            dbg_once!();
+        });
+        assert_eq!(&output[..], "");
+    }
+}
@@ -0,0 +1,74 @@
+#![cfg(feature = "tracing")]
+use dbg_if::{dbg_if_hash_ne, write};
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+};
+use tracing::{
+    field::{Field, Visit},
+    span, Event, Metadata, Subscriber,
+};
+
+#[derive(Clone, Default)]
+struct Events(Arc<Mutex<Vec<String>>>);
+
+struct Line(String);
+
+impl Visit for Line {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "line" {
+            // The line moves as this file is edited.
+            self.0.push_str(" line");
+        } else {
+            self.0.push_str(&format!(" {}={:?}", field.name(), value));
+        }
+    }
+}
+
+impl Subscriber for Events {
+    fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, _span: &span::Attributes<'_>) -> span::Id {
+        span::Id::from_u64(1)
+    }
+
+    fn record(&self, _span: &span::Id, _values: &span::Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &span::Id, _follows: &span::Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let metadata = event.metadata();
+        let mut line = Line(format!("{} {}", metadata.level(), metadata.target()));
+        event.record(&mut line);
+        self.0.lock().unwrap().push(line.0);
+    }
+
+    fn enter(&self, _span: &span::Id) {}
+
+    fn exit(&self, _span: &span::Id) {}
+}
+
+#[test]
+fn test_tracing_sink() {
+    let events = Events::default();
+    write::set_sink(write::tracing_sink);
+
+    tracing::subscriber::with_default(events.clone(), || {
+        fn f(s: &str) {
+            dbg_if_hash_ne!(s);
+        }
+        f("a");
+        f("a");
+        f("b");
+    });
+
+    assert_eq!(
+        *events.0.lock().unwrap(),
+        [
+            "DEBUG dbg_if message=s = \"a\" file=\"tests/tracing.rs\" line column=13 expr=\"s\" value=\"a\"",
+            "DEBUG dbg_if message=s = \"b\" file=\"tests/tracing.rs\" line column=13 expr=\"s\" value=\"b\"",
+        ]
+    );
+}
@@ -0,0 +1,67 @@
+#![cfg(feature = "tui")]
+use dbg_if::{
+    capture, dbg_if_ne,
+    tui::{
+        ratatui::{backend::TestBackend, crossterm::event::KeyCode, Terminal},
+        Dashboard,
+    },
+};
+
+fn f(x: u32) {
+    dbg_if_ne!(x, u32);
+}
+
+fn draw(terminal: &mut Terminal<TestBackend>, dashboard: &mut Dashboard) -> Vec<String> {
+    terminal.draw(|frame| dashboard.render(frame)).unwrap();
+    let buffer = terminal.backend().buffer();
+    (0..buffer.area.height)
+        .map(|y| {
+            (0..buffer.area.width)
+                .map(|x| buffer[(x, y)].symbol())
+                .collect::<String>()
+        })
+        .collect()
+}
+
+#[test]
+fn test_dashboard() {
+    let mut dashboard = Dashboard::new();
+    let mut terminal = Terminal::new(TestBackend::new(70, 10)).unwrap();
+    for x in [1, 5, 3, 3] {
+        capture(|| f(x));
+        dashboard.sample();
+    }
+
+    let lines = draw(&mut terminal, &mut dashboard);
+    assert!(lines[0].contains(" dbg_if "), "{:#?}", lines);
+    assert!(lines[1].contains("expr") && lines[1].contains("changes"));
+    let row = |lines: &[String]| {
+        lines[2]
+            .trim_matches('│')
+            .split_whitespace()
+            .map(String::from)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(row(&lines), ["on", "x", "tests/tui.rs:11:5", "3", "3"], "{:#?}", lines);
+    assert!(lines[5].contains(" x "));
+    // The sparkline rises to 5 and falls to 3.
+    assert!(lines[8].contains('█'), "{:#?}", lines);
+
+    // Turn `x` off; it neither prints nor keeps its value.
+    assert!(dashboard.key(KeyCode::Char(' ')));
+    let site = dashboard.selected().unwrap();
+    assert!(!site.is_enabled());
+    assert!(capture(|| f(9)).is_empty());
+    assert_eq!(site.last_observed().unwrap(), "3");
+    let lines = draw(&mut terminal, &mut dashboard);
+    assert_eq!(row(&lines)[0], "off");
+
+    assert!(dashboard.key(KeyCode::Enter));
+    assert!(site.is_enabled());
+    assert!(!dashboard.key(KeyCode::Char('q')));
+
+    // Dropping the dashboard stops keeping values.
+    drop(dashboard);
+    capture(|| f(11));
+    assert_eq!(site.last_observed().unwrap(), "3");
+}
@@ -0,0 +1,49 @@
+#![cfg(feature = "std")]
+mod common;
+
+use common::{collect_lines, run_once, take_lines};
+use dbg_if::{dbg_if_ne, report, watch};
+use std::time::Duration;
+
+fn run() -> &'static [String] {
+    run_once(|| {
+        let watch = watch::start(Duration::from_millis(10));
+        for i in 0..100u32 {
+            dbg_if_ne!(i / 10, u32);
+        }
+        std::thread::sleep(Duration::from_millis(50));
+        drop(watch);
+    })
+}
+
+#[test]
+fn test_no_output_while_watching() {
+    let _serial = collect_lines();
+    let lines = run();
+    assert!(lines.len() >= 6, "{:?}", lines);
+    assert!(lines.iter().all(|line| !line.starts_with('[')));
+}
+
+#[test]
+fn test_last_table_is_up_to_date() {
+    let _serial = collect_lines();
+    let lines = run();
+    assert_eq!(
+        lines[lines.len() - 3..],
+        [
+            "dbg_if: watch +@",
+            "location              expr    hits  changes  value",
+            "@  i / 10   100       10  9",
+        ]
+    );
+}
+
+#[test]
+fn test_prints_again_after_drop() {
+    let _serial = collect_lines();
+    run();
+    dbg_if_ne!(7u32, u32);
+    assert_eq!(take_lines(), ["[@] 7u32 = 7"]);
+    let site = report::sites().find(|site| site.expr() == "7u32").unwrap();
+    assert_eq!(site.last_observed(), None);
+}